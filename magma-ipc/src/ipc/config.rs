@@ -0,0 +1,11 @@
+use super::generated::config::Event;
+
+impl Into<String> for Event {
+    fn into(self) -> String {
+        match self {
+            Event::Reloaded => "reloaded".to_owned(),
+            Event::Error { message: _ } => "error".to_owned(),
+            Event::Warning { message: _ } => "warning".to_owned(),
+        }
+    }
+}
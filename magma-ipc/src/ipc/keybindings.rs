@@ -0,0 +1,10 @@
+use super::generated::keybindings::Event;
+
+impl Into<String> for Event {
+    fn into(self) -> String {
+        match self {
+            Event::ActiveMode { name: _ } => "active_mode".to_owned(),
+            Event::PendingKeys { keys: _ } => "pending_keys".to_owned(),
+        }
+    }
+}
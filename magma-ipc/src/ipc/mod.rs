@@ -9,4 +9,6 @@ pub mod generated {
     wayland_scanner::generate_client_code!("../ipc.xml");
 }
 
+pub mod config;
+pub mod keybindings;
 pub mod workspaces;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use ipc::generated::{workspaces::{Workspaces, Event as WorkspacesEvent}, magma_ipc::MagmaIpc};
+use ipc::generated::{workspaces::{Workspaces, Event as WorkspacesEvent}, config::{Config, Event as ConfigEvent}, keybindings::{Keybindings, Event as KeybindingsEvent}, magma_ipc::MagmaIpc};
 use wayland_client::{Connection, Dispatch, protocol::wl_registry, QueueHandle, globals::{registry_queue_init, GlobalListContents}};
 
 mod ipc;
@@ -54,6 +54,59 @@ impl Dispatch<Workspaces, ()> for State {
     }
 }
 
+impl Dispatch<Config, ()> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &Config,
+        event: ConfigEvent,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            ConfigEvent::Reloaded => {
+                if "reloaded" == state.0 {
+                    println!("reloaded")
+                }
+            },
+            ConfigEvent::Error { message } => {
+                if "error" == state.0 {
+                    println!("{}", message)
+                }
+            },
+            ConfigEvent::Warning { message } => {
+                if "warning" == state.0 {
+                    println!("{}", message)
+                }
+            },
+        }
+    }
+}
+
+impl Dispatch<Keybindings, ()> for State {
+    fn event(
+        state: &mut Self,
+        _proxy: &Keybindings,
+        event: KeybindingsEvent,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            KeybindingsEvent::ActiveMode { name } => {
+                if "active_mode" == state.0 {
+                    println!("{}", name)
+                }
+            },
+            KeybindingsEvent::PendingKeys { keys } => {
+                if "pending_keys" == state.0 {
+                    println!("{}", keys)
+                }
+            },
+        }
+    }
+}
+
 fn main() {
     let conn = Connection::connect_to_env().unwrap();
     let mut event_queue = conn.new_event_queue();
@@ -67,6 +120,12 @@ fn main() {
         Some("workspace") => {
             ipc.workspaces(&qh, ());
         }
+        Some("config") => {
+            ipc.config(&qh, ());
+        }
+        Some("keybindings") => {
+            ipc.keybindings(&qh, ());
+        }
         Some(_) => {
             todo!()
         }
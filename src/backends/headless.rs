@@ -0,0 +1,371 @@
+use std::{collections::HashMap, time::Duration};
+
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        egl::{EGLContext, EGLDevice, EGLDisplay},
+        renderer::{
+            self,
+            damage::OutputDamageTracker,
+            gles::{GlesError, GlesRenderer, GlesTexture},
+            Bind, BufferType, ExportMem, Offscreen,
+        },
+    },
+    desktop::{layer_map_for_output, space::SpaceElement},
+    output::{Mode, Output, PhysicalProperties, Subpixel},
+    reexports::{
+        calloop::{
+            timer::{TimeoutAction, Timer},
+            EventLoop,
+        },
+        wayland_server::{
+            protocol::{wl_output::WlOutput, wl_shm},
+            Display,
+        },
+    },
+    utils::{Physical, Point, Rectangle, Size, Transform},
+    wayland::shm,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    cli::Args,
+    config::OutputConfig,
+    delegate_screencopy_manager,
+    state::{Backend, CalloopData, MagmaState},
+    utils::{
+        protocols::screencopy::{frame::Screencopy, ScreencopyHandler, ScreencopyManagerState},
+        render::{output_render_elements, CustomRenderElements},
+    },
+};
+
+/// Size used for the virtual output if `MAGMA_HEADLESS_OUTPUTS` is not set.
+const DEFAULT_OUTPUT_SIZE: (i32, i32) = (1920, 1080);
+
+pub struct HeadlessData {
+    renderer: GlesRenderer,
+    outputs: Vec<HeadlessOutput>,
+}
+
+/// A virtual output together with the offscreen buffer it is rendered into.
+pub struct HeadlessOutput {
+    pub output: Output,
+    damage_tracker: OutputDamageTracker,
+    buffer: GlesTexture,
+}
+
+impl Backend for HeadlessData {
+    fn seat_name(&self) -> String {
+        "headless".to_string()
+    }
+
+    fn apply_output_config(&mut self, outputs: &HashMap<String, OutputConfig>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for headless in self.outputs.iter_mut() {
+            let Some(config) = outputs.get(&headless.output.name()) else {
+                continue;
+            };
+            let mode = config.output_mode();
+            if headless.output.current_mode() == Some(mode) {
+                continue;
+            }
+
+            match create_buffer(&mut self.renderer, mode.size) {
+                Ok(buffer) => headless.buffer = buffer,
+                Err(err) => {
+                    let name = headless.output.name();
+                    problems.push(format!("output {}: failed to resize: {}", name, err));
+                    continue;
+                }
+            }
+            headless.output.change_current_state(Some(mode), None, None, None);
+            headless.output.set_preferred(mode);
+            headless.damage_tracker = OutputDamageTracker::from_output(&headless.output);
+        }
+        problems
+    }
+}
+
+impl HeadlessData {
+    /// Creates a software renderer and one virtual output per entry of `sizes`.
+    pub fn new(sizes: &[Size<i32, Physical>]) -> Self {
+        let device = EGLDevice::enumerate()
+            .expect("Failed to enumerate EGL devices")
+            .find(|device| device.is_software())
+            .expect("No software EGL device found");
+        let egl_display = EGLDisplay::new(device).expect("Failed to create EGL display");
+        let context = EGLContext::new(&egl_display).expect("Failed to create EGL context");
+        let mut renderer = unsafe { GlesRenderer::new(context) }.expect("Failed to create renderer");
+
+        let outputs = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let mode = Mode {
+                    size: *size,
+                    refresh: 60_000,
+                };
+                let output = Output::new(
+                    format!("HEADLESS-{}", i + 1),
+                    PhysicalProperties {
+                        size: (0, 0).into(),
+                        subpixel: Subpixel::Unknown,
+                        make: "Magma".into(),
+                        model: "Headless".into(),
+                    },
+                );
+                output.change_current_state(
+                    Some(mode),
+                    Some(Transform::Normal),
+                    None,
+                    None,
+                );
+                output.set_preferred(mode);
+
+                let buffer = create_buffer(&mut renderer, *size)
+                    .expect("Failed to create offscreen buffer");
+
+                HeadlessOutput {
+                    damage_tracker: OutputDamageTracker::from_output(&output),
+                    output,
+                    buffer,
+                }
+            })
+            .collect();
+
+        HeadlessData { renderer, outputs }
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = &Output> {
+        self.outputs.iter().map(|o| &o.output)
+    }
+}
+
+fn create_buffer(
+    renderer: &mut GlesRenderer,
+    size: Size<i32, Physical>,
+) -> Result<GlesTexture, GlesError> {
+    Offscreen::<GlesTexture>::create_buffer(
+        renderer,
+        Fourcc::Abgr8888,
+        size.to_logical(1).to_buffer(1, Transform::Normal),
+    )
+}
+
+/// Parses `MAGMA_HEADLESS_OUTPUTS`, a comma separated list of `WIDTHxHEIGHT` sizes.
+fn output_sizes_from_env() -> Vec<Size<i32, Physical>> {
+    let sizes = std::env::var("MAGMA_HEADLESS_OUTPUTS")
+        .ok()
+        .map(|var| {
+            var.split(',')
+                .filter_map(|size| {
+                    let (w, h) = size.trim().split_once('x')?;
+                    match (w.parse(), h.parse()) {
+                        (Ok(w), Ok(h)) => Some(Size::from((w, h))),
+                        _ => {
+                            warn!("Ignoring invalid headless output size \"{}\"", size);
+                            None
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if sizes.is_empty() {
+        vec![DEFAULT_OUTPUT_SIZE.into()]
+    } else {
+        sizes
+    }
+}
+
+/// Sets up a `MagmaState` on a headless backend without running the event loop.
+pub fn init_headless_state(
+    event_loop: &mut EventLoop<'static, CalloopData<HeadlessData>>,
+    sizes: &[Size<i32, Physical>],
+    args: &Args,
+) -> CalloopData<HeadlessData> {
+    let mut display: Display<MagmaState<HeadlessData>> = Display::new().unwrap();
+
+    let headlessdata = HeadlessData::new(sizes);
+    for output in headlessdata.outputs() {
+        let _global = output.create_global::<MagmaState<HeadlessData>>(&display.handle());
+    }
+
+    let mut state = MagmaState::new(event_loop.handle(), event_loop.get_signal(), &mut display, headlessdata, args);
+    ScreencopyManagerState::new::<MagmaState<HeadlessData>>(&display.handle());
+    state.backend_data.apply_output_config(&state.config.outputs);
+
+    let outputs: Vec<Output> = state.backend_data.outputs().cloned().collect();
+    for output in outputs {
+        let config = state.config.outputs.get(&output.name());
+        state.workspaces.add_output(output, config, state.config.gaps);
+    }
+
+    CalloopData { state, display }
+}
+
+pub fn init_headless(args: &Args) {
+    let mut event_loop: EventLoop<CalloopData<HeadlessData>> = EventLoop::try_new().unwrap();
+
+    let mut data = init_headless_state(&mut event_loop, &output_sizes_from_env(), args);
+
+    std::env::set_var("WAYLAND_DISPLAY", &data.state.socket_name);
+    info!("Headless outputs: {}", data.state.backend_data.outputs.len());
+
+    for command in &data.state.config.autostart {
+        if let Err(err) = std::process::Command::new("/bin/sh")
+            .arg("-c")
+            .arg(command)
+            .spawn()
+        {
+            warn!("Failed to spawn \"{}\": {}", command, err);
+        }
+    }
+
+    let timer = Timer::immediate();
+    event_loop
+        .handle()
+        .insert_source(timer, move |_, _, data| {
+            data.state.render_headless();
+            TimeoutAction::ToDuration(Duration::from_millis(16))
+        })
+        .unwrap();
+
+    event_loop
+        .run(None, &mut data, move |data| {
+            data.state.workspaces.all_windows().for_each(|e| e.refresh());
+            data.state.popup_manager.cleanup();
+            data.display.flush_clients().unwrap();
+        })
+        .unwrap();
+}
+
+impl MagmaState<HeadlessData> {
+    /// Renders every virtual output into its offscreen buffer and sends frame callbacks.
+    pub fn render_headless(&mut self) {
+        for i in 0..self.backend_data.outputs.len() {
+            self.render_output(i, None);
+        }
+    }
+
+    fn render_output(&mut self, index: usize, screencopy: Option<Screencopy>) {
+        let backend = &mut self.backend_data;
+        let headless_output = &mut backend.outputs[index];
+        let output = &headless_output.output;
+        let workspaces = self.workspaces.rendered_on(output);
+
+        if let Err(err) = backend.renderer.bind(headless_output.buffer.clone()) {
+            error!("Failed to bind offscreen buffer: {}", err);
+            return;
+        }
+
+        let renderelements: Vec<CustomRenderElements<_>> =
+            output_render_elements(&mut backend.renderer, output, &workspaces);
+
+        let damage = match headless_output.damage_tracker.render_output(
+            &mut backend.renderer,
+            1,
+            &renderelements,
+            [0.1, 0.1, 0.1, 1.0],
+        ) {
+            Ok((damage, _)) => damage,
+            Err(err) => {
+                warn!("Error during rendering: {:?}", err);
+                return;
+            }
+        };
+
+        // Copy framebuffer for screencopy.
+        if let Some(mut screencopy) = screencopy {
+            if let Some(damage) = damage {
+                screencopy.damage(&damage);
+            }
+
+            let shm_buffer = screencopy.buffer();
+
+            // Ignore unknown buffer types.
+            let buffer_type = renderer::buffer_type(shm_buffer);
+            if !matches!(buffer_type, Some(BufferType::Shm)) {
+                warn!("Unsupported buffer type: {:?}", buffer_type);
+                return;
+            }
+
+            let region = screencopy.region();
+            let region = Rectangle {
+                loc: Point::from((region.loc.x, region.loc.y)),
+                size: Size::from((region.size.w, region.size.h)),
+            };
+            let mapping = match backend.renderer.copy_framebuffer(region, Fourcc::Argb8888) {
+                Ok(mapping) => mapping,
+                Err(err) => {
+                    warn!("Failed to copy framebuffer: {}", err);
+                    return;
+                }
+            };
+            let buffer = backend.renderer.map_texture(&mapping);
+
+            // Copy offscreen buffer's content to the SHM buffer.
+            let copied = shm::with_buffer_contents_mut(shm_buffer, |shm_buffer_ptr, shm_len, buffer_data| {
+                // Ensure SHM buffer is in an acceptable format.
+                if buffer_data.format != wl_shm::Format::Argb8888
+                    || buffer_data.stride != region.size.w * 4
+                    || buffer_data.height != region.size.h
+                    || shm_len as i32 != buffer_data.stride * buffer_data.height
+                {
+                    error!("Invalid buffer format");
+                    return false;
+                }
+
+                match buffer {
+                    Ok(buffer) => {
+                        unsafe { shm_buffer_ptr.copy_from(buffer.as_ptr(), shm_len) };
+                        true
+                    }
+                    Err(_) => false,
+                }
+            });
+
+            // Mark screencopy frame as successful.
+            if let Ok(true) = copied {
+                screencopy.submit();
+            }
+        }
+
+        workspace.into_iter().flat_map(|w| w.windows()).for_each(|window| {
+            window.send_frame(
+                output,
+                self.start_time.elapsed(),
+                Some(Duration::ZERO),
+                |_, _| Some(output.clone()),
+            );
+        });
+        for layer_surface in layer_map_for_output(output).layers() {
+            layer_surface.send_frame(
+                output,
+                self.start_time.elapsed(),
+                Some(Duration::ZERO),
+                |_, _| Some(output.clone()),
+            );
+        }
+    }
+}
+
+impl ScreencopyHandler for MagmaState<HeadlessData> {
+    fn output(&mut self, output: &WlOutput) -> &Output {
+        self.workspaces.outputs().find(|o| o.owns(output)).unwrap()
+    }
+
+    fn frame(&mut self, frame: Screencopy) {
+        if let Some(index) = self
+            .backend_data
+            .outputs
+            .iter()
+            .position(|o| o.output == frame.output)
+        {
+            self.render_output(index, Some(frame));
+        }
+    }
+}
+
+delegate_screencopy_manager!(MagmaState<HeadlessData>);
@@ -1,2 +1,3 @@
+pub mod headless;
 pub mod udev;
 pub mod winit;
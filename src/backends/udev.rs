@@ -13,25 +13,29 @@ use smithay::{
         },
         drm::{self, DrmDevice, DrmDeviceFd, DrmNode, NodeType, compositor::{DrmCompositor}, DrmError},
         egl::{EGLDevice, EGLDisplay},
+        input::InputEvent,
         libinput::{LibinputInputBackend, LibinputSessionInterface},
         renderer::{
-            element::{texture::{TextureBuffer, TextureRenderElement}, surface::WaylandSurfaceRenderElement, AsRenderElements},
+            element::texture::{TextureBuffer, TextureRenderElement},
             gles::{GlesRenderer, GlesTexture},
             multigpu::{gbm::GbmGlesBackend, GpuManager, MultiRenderer, MultiTexture}, ImportDma, self, Bind,  Offscreen, BufferType, ExportMem,
         },
         session::{libseat::LibSeatSession, Event as SessionEvent, Session},
         udev::{self, UdevBackend, UdevEvent}, SwapBuffersError,
     },
-    desktop::{space::SpaceElement, layer_map_for_output, LayerSurface},
+    desktop::{space::SpaceElement, layer_map_for_output},
     output::{Mode as WlMode, Output, PhysicalProperties},
     reexports::{
         calloop::{EventLoop, LoopHandle, RegistrationToken, timer::{Timer, TimeoutAction}},
-        drm::{control::{crtc::{self, Handle}, ModeTypeFlags}, Device as DrmDeviceTrait, SystemError},
-        input::Libinput,
+        drm::{control::{crtc::{self, Handle}, Device as ControlDevice, Mode as DrmMode, ModeTypeFlags}, Device as DrmDeviceTrait, SystemError},
+        input::{
+            AccelProfile as LibinputAccelProfile, Device as LibinputDevice,
+            DeviceCapability as LibinputCapability, DeviceConfigResult, Libinput,
+        },
         nix::fcntl::OFlag,
         wayland_server::{Display, DisplayHandle, backend::GlobalId, protocol::{wl_output::WlOutput, wl_shm}}, wayland_protocols::wp::linux_dmabuf::zv1::server::zwp_linux_dmabuf_feedback_v1,
     },
-    utils::{DeviceFd, Scale, Transform, Size, Rectangle, Point}, wayland::{shell::wlr_layer::Layer, dmabuf::{DmabufGlobal, DmabufState, DmabufHandler, ImportError, DmabufFeedbackBuilder, DmabufFeedback}, shm}, delegate_dmabuf,
+    utils::{DeviceFd, Scale, Transform, Size, Rectangle, Point}, wayland::{dmabuf::{DmabufGlobal, DmabufState, DmabufHandler, ImportError, DmabufFeedbackBuilder, DmabufFeedback}, shm}, delegate_dmabuf,
 };
 use smithay_drm_extras::{
     drm_scanner::{self, DrmScanEvent, DrmScanner},
@@ -40,8 +44,10 @@ use smithay_drm_extras::{
 use tracing::{error, info, warn, trace};
 
 use crate::{
+    cli::Args,
+    config::{AccelProfile, Config, OutputConfig},
     state::{Backend, CalloopData, MagmaState},
-    utils::{render::CustomRenderElements, protocols::screencopy::{ScreencopyManagerState, frame::Screencopy, ScreencopyHandler}}, delegate_screencopy_manager,
+    utils::{render::{CustomRenderElements, output_render_elements}, protocols::screencopy::{ScreencopyManagerState, frame::Screencopy, ScreencopyHandler}}, delegate_screencopy_manager,
 };
 pub type GbmDrmCompositor = DrmCompositor<
     GbmAllocator<DrmDeviceFd>,
@@ -65,6 +71,7 @@ pub struct UdevData {
     gpus: GpuManager<GbmGlesBackend<GlesRenderer>>,
     devices: HashMap<DrmNode, Device>,
     dmabuf_state: Option<(DmabufState, DmabufGlobal)>,
+    input_devices: Vec<LibinputDevice>,
 }
 
 impl DmabufHandler for MagmaState<UdevData> {
@@ -87,6 +94,171 @@ impl Backend for UdevData {
     fn seat_name(&self) -> String {
         self.session.seat()
     }
+
+    fn check_output_config(&self, outputs: &HashMap<String, OutputConfig>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for device in self.devices.values() {
+            for surface in device.surfaces.values() {
+                let Some(output_config) = outputs.get(&surface.output.name()) else {
+                    continue;
+                };
+                let modes = connector_modes(&device.drm, surface);
+                if configured_mode(&modes, output_config).is_none() {
+                    let size = output_config.mode_size();
+                    problems.push(format!(
+                        "output {}: no {}x{} mode is supported",
+                        surface.output.name(),
+                        size.w,
+                        size.h
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    fn apply_output_config(&mut self, outputs: &HashMap<String, OutputConfig>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for device in self.devices.values_mut() {
+            for surface in device.surfaces.values_mut() {
+                let Some(output_config) = outputs.get(&surface.output.name()) else {
+                    continue;
+                };
+                let modes = connector_modes(&device.drm, surface);
+                let Some(drm_mode) = configured_mode(&modes, output_config) else {
+                    continue;
+                };
+                if surface.compositor.surface().pending_mode() == drm_mode {
+                    continue;
+                }
+
+                if let Err(err) = surface.compositor.use_mode(drm_mode) {
+                    problems.push(format!(
+                        "output {}: failed to change the mode: {}",
+                        surface.output.name(),
+                        err
+                    ));
+                    continue;
+                }
+                let output_mode = WlMode::from(drm_mode);
+                surface.output.set_preferred(output_mode);
+                surface.output.change_current_state(Some(output_mode), None, None, None);
+            }
+        }
+        problems
+    }
+
+    fn apply_input_config(&mut self, config: &Config) -> Vec<String> {
+        self.input_devices
+            .iter_mut()
+            .flat_map(|device| configure_input_device(device, config))
+            .collect()
+    }
+}
+
+/// Kinds of a libinput device as used in the `input` config, most specific first.
+fn input_device_kinds(device: &LibinputDevice) -> Vec<&'static str> {
+    let mut kinds = Vec::new();
+    if device.config_tap_finger_count() > 0 {
+        kinds.push("touchpad");
+    }
+    if device.has_capability(LibinputCapability::Pointer) {
+        kinds.push("pointer");
+    }
+    if device.has_capability(LibinputCapability::Keyboard) {
+        kinds.push("keyboard");
+    }
+    if device.has_capability(LibinputCapability::Touch) {
+        kinds.push("touch");
+    }
+    if device.has_capability(LibinputCapability::TabletTool) {
+        kinds.push("tablet");
+    }
+    kinds
+}
+
+/// Applies the settings of `device` from the `input` config, settings that
+/// are left out are reset to the device defaults.
+///
+/// Returns the settings the device refused.
+fn configure_input_device(device: &mut LibinputDevice, config: &Config) -> Vec<String> {
+    let name = device.name().to_string();
+    let input_config = config.input_config(&name, &input_device_kinds(device));
+    let mut problems = Vec::new();
+    let mut check = |setting: &str, result: DeviceConfigResult| {
+        if let Err(err) = result {
+            problems.push(format!("input {}: failed to set {}: {:?}", name, setting, err));
+        }
+    };
+
+    if device.config_tap_finger_count() > 0 {
+        let tap = input_config.tap.unwrap_or_else(|| device.config_tap_default_enabled());
+        check("tap", device.config_tap_set_enabled(tap));
+    }
+    if device.config_scroll_has_natural_scroll() {
+        let natural_scroll = input_config
+            .natural_scroll
+            .unwrap_or_else(|| device.config_scroll_default_natural_scroll_enabled());
+        check(
+            "natural_scroll",
+            device.config_scroll_set_natural_scroll_enabled(natural_scroll),
+        );
+    }
+    if device.config_accel_is_available() {
+        let profile = match input_config.accel_profile {
+            Some(AccelProfile::Flat) => Some(LibinputAccelProfile::Flat),
+            Some(AccelProfile::Adaptive) => Some(LibinputAccelProfile::Adaptive),
+            None => device.config_accel_default_profile(),
+        };
+        if let Some(profile) = profile {
+            check("accel_profile", device.config_accel_set_profile(profile));
+        }
+        let speed = input_config
+            .accel_speed
+            .unwrap_or_else(|| device.config_accel_default_speed());
+        check("accel_speed", device.config_accel_set_speed(speed));
+    }
+    if device.config_left_handed_is_available() {
+        let left_handed = input_config
+            .left_handed
+            .unwrap_or_else(|| device.config_left_handed_default());
+        check("left_handed", device.config_left_handed_set(left_handed));
+    }
+    if device.config_dwt_is_available() {
+        let dwt = input_config
+            .disable_while_typing
+            .unwrap_or_else(|| device.config_dwt_default_enabled());
+        check("disable_while_typing", device.config_dwt_set_enabled(dwt));
+    }
+    problems
+}
+
+/// Modes supported by the connectors driven by `surface`.
+fn connector_modes(drm: &DrmDevice, surface: &Surface) -> Vec<DrmMode> {
+    surface
+        .compositor
+        .surface()
+        .current_connectors()
+        .into_iter()
+        .filter_map(|connector| drm.get_connector(connector, false).ok())
+        .flat_map(|info| info.modes().to_vec())
+        .collect()
+}
+
+/// Finds the mode of `modes` with the configured size and the refresh
+/// rate closest to the configured one (e.g. to match 59.98 as 60).
+fn configured_mode(modes: &[DrmMode], output_config: &OutputConfig) -> Option<DrmMode> {
+    modes
+        .iter()
+        .filter(|mode| {
+            let (x, y) = mode.size();
+            Size::from((x as i32, y as i32)) == output_config.mode_size()
+        })
+        .min_by_key(|mode| {
+            let refresh_rate = WlMode::from(**mode).refresh;
+            (output_config.mode_refresh() as i32 - refresh_rate as i32).abs()
+        })
+        .copied()
 }
 pub struct Device {
     pub surfaces: HashMap<crtc::Handle, Surface>,
@@ -97,7 +269,7 @@ pub struct Device {
     pub registration_token: RegistrationToken,
 }
 
-pub fn init_udev() {
+pub fn init_udev(args: &Args) {
     let mut event_loop: EventLoop<CalloopData<UdevData>> = EventLoop::try_new().unwrap();
     let mut display: Display<MagmaState<UdevData>> = Display::new().unwrap();
 
@@ -127,9 +299,10 @@ pub fn init_udev() {
         gpus,
         devices: HashMap::new(),
         dmabuf_state: None,
+        input_devices: Vec::new(),
     };
 
-    let mut state = MagmaState::new(event_loop.handle(), event_loop.get_signal(), &mut display, data);
+    let mut state = MagmaState::new(event_loop.handle(), event_loop.get_signal(), &mut display, data, args);
     ScreencopyManagerState::new::<MagmaState<UdevData>>(&display.handle());
     
     /*
@@ -147,6 +320,20 @@ pub fn init_udev() {
     event_loop
         .handle()
         .insert_source(libinput_backend, move |event, _, calloopdata| {
+            let state = &mut calloopdata.state;
+            match &event {
+                InputEvent::DeviceAdded { device } => {
+                    let mut device = device.clone();
+                    for problem in configure_input_device(&mut device, &state.config) {
+                        warn!("{}", problem);
+                    }
+                    state.backend_data.input_devices.push(device);
+                }
+                InputEvent::DeviceRemoved { device } => {
+                    state.backend_data.input_devices.retain(|known| known != device);
+                }
+                _ => {}
+            }
             if let Some(vt) = calloopdata.state.process_input_event_udev(event) {
                 info!(to = vt, "Trying to switch vt");
                 if let Err(err) = calloopdata.state.backend_data.session.change_vt(vt) {
@@ -363,20 +550,9 @@ impl MagmaState<UdevData> {
                     connector.interface_id()
                 );
         
-                let drm_mode = if self.config.outputs.contains_key(&name) {
-                    let output_config = &self.config.outputs[&name];
-                    *connector
-                        .modes()
-                        .iter()
-                        .filter(|mode| {
-                            let (x, y) = mode.size();
-                            Size::from((x as i32, y as i32)) == output_config.mode_size()
-                        })
-                            // and then select the closest refresh rate (e.g. to match 59.98 as 60)
-                        .min_by_key(|mode| {
-                            let refresh_rate = WlMode::from(**mode).refresh;
-                            (output_config.mode_refresh() as i32 - refresh_rate as i32).abs()
-                        }).expect("No matching mode found for output config")
+                let drm_mode = if let Some(output_config) = self.config.outputs.get(&name) {
+                    configured_mode(connector.modes(), output_config)
+                        .expect("No matching mode found for output config")
                 } else {
                     *connector
                     .modes()
@@ -486,10 +662,8 @@ impl MagmaState<UdevData> {
                     pointer_texture,
                 };
                 
-                for workspace in self.workspaces.iter() {
-                    workspace.remove_outputs();
-                    workspace.add_output(output.clone())
-                }
+                let output_config = self.config.outputs.get(&output.name());
+                self.workspaces.add_output(output.clone(), output_config, self.config.gaps);
 
                 device.surfaces.insert(crtc, surface);
 
@@ -502,7 +676,10 @@ impl MagmaState<UdevData> {
             DrmScanEvent::Disconnected {
                 crtc: Some(crtc), ..
             } => {
-                device.surfaces.remove(&crtc);
+                if let Some(surface) = device.surfaces.remove(&crtc) {
+                    self.dh.disable_global::<MagmaState<UdevData>>(surface.global.clone());
+                    self.workspaces.remove_output(&surface.output, self.config.gaps);
+                }
             }
             _ => {}
         }
@@ -636,13 +813,15 @@ impl MagmaState<UdevData> {
         let device = self.backend_data.devices.get_mut(&node).unwrap();
         let surface = device.surfaces.get_mut(&crtc).unwrap();
         let mut renderer = self.backend_data.gpus.single_renderer(&device.render_node).unwrap();
-        let output = self.workspaces.current().outputs().next().unwrap();
+        let output = surface.output.clone();
+        let output = &output;
+        let workspaces = self.workspaces.rendered_on(output);
 
         let mut renderelements: Vec<CustomRenderElements<MultiRenderer<_,_>>> = vec![];
 
         renderelements.append(&mut vec![CustomRenderElements::<MultiRenderer<_,_>>::from(
             TextureRenderElement::from_texture_buffer(
-                self.pointer_location.to_physical(Scale::from(1.0)),
+                (self.pointer_location - output.current_location().to_f64()).to_physical(Scale::from(1.0)),
                 &surface.pointer_texture,
                 None,
                 None,
@@ -650,54 +829,12 @@ impl MagmaState<UdevData> {
             ),
         )]);
 
-        let layer_map = layer_map_for_output(&output);
-        let (lower, upper): (Vec<&LayerSurface>, Vec<&LayerSurface>) = layer_map
-            .layers()
-            .rev()
-            .partition(|s| matches!(s.layer(), Layer::Background | Layer::Bottom));
-
-        renderelements.extend(
-            upper
-                .into_iter()
-                .filter_map(|surface| {
-                    layer_map
-                        .layer_geometry(surface)
-                        .map(|geo| (geo.loc, surface))
-                })
-                .flat_map(|(loc, surface)| {
-                    AsRenderElements::<MultiRenderer<_,_>>::render_elements::<WaylandSurfaceRenderElement<MultiRenderer<_,_>>>(
-                        surface,
-                        &mut renderer,
-                        loc.to_physical_precise_round(1),
-                        Scale::from(1.0),
-                    )
-                    .into_iter()
-                    .map(CustomRenderElements::Surface)
-                }),
-        );
-
-        renderelements.extend(self.workspaces.current().render_elements(&mut renderer));
+        renderelements.extend(output_render_elements::<_, CustomRenderElements<_>>(
+            &mut renderer,
+            output,
+            &workspaces,
+        ));
 
-        renderelements.extend(
-            lower
-                .into_iter()
-                .filter_map(|surface| {
-                    layer_map
-                        .layer_geometry(surface)
-                        .map(|geo| (geo.loc, surface))
-                })
-                .flat_map(|(loc, surface)| {
-                    AsRenderElements::<MultiRenderer<_,_>>::render_elements::<WaylandSurfaceRenderElement<MultiRenderer<_,_>>>(
-                        surface,
-                        &mut renderer,
-                        loc.to_physical_precise_round(1),
-                        Scale::from(1.0),
-                    )
-                    .into_iter()
-                    .map(CustomRenderElements::Surface)
-                }),
-        );
-        
         let frame_result = surface.compositor
             .render_frame::<_, _, GlesTexture>(
                 &mut renderer,
@@ -810,7 +947,7 @@ impl MagmaState<UdevData> {
                 .expect("failed to schedule frame timer");
         }
 
-        self.workspaces.current().windows().for_each(|window| {
+        workspace.into_iter().flat_map(|w| w.windows()).for_each(|window| {
             window.send_frame(
                 &output,
                 self.start_time.elapsed(),
@@ -818,7 +955,7 @@ impl MagmaState<UdevData> {
                 |_, _| Some(output.clone()),
             );
         });
-        for layer_surface in layer_map.layers() {
+        for layer_surface in layer_map_for_output(output).layers() {
             layer_surface.send_frame(
                 &output,
                 self.start_time.elapsed(),
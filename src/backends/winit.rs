@@ -3,12 +3,11 @@ use std::time::Duration;
 use smithay::{
     backend::{
         renderer::{
-            damage::OutputDamageTracker, element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
-            gles::GlesRenderer,
+            damage::OutputDamageTracker, gles::GlesRenderer,
         },
         winit::{self, WinitError, WinitEvent, WinitEventLoop, WinitGraphicsBackend},
     },
-    desktop::{space::SpaceElement, layer_map_for_output, LayerSurface},
+    desktop::space::SpaceElement,
     output::{Mode, Output, PhysicalProperties, Subpixel},
     reexports::{
         calloop::{
@@ -17,7 +16,7 @@ use smithay::{
         },
         wayland_server::Display,
     },
-    utils::{Rectangle, Transform, Scale}, wayland::shell::wlr_layer::Layer,
+    utils::{Rectangle, Transform},
 };
 
 pub struct WinitData {
@@ -30,9 +29,9 @@ impl Backend for WinitData {
         "winit".to_string()
     }
 }
-use crate::{state::Backend, CalloopData, MagmaState};
+use crate::{cli::Args, state::{Backend, CalloopData, MagmaState}, utils::render::{output_render_elements, CustomRenderElements}};
 
-pub fn init_winit() {
+pub fn init_winit(args: &Args) {
     let mut event_loop: EventLoop<CalloopData<WinitData>> = EventLoop::try_new().unwrap();
 
     let mut display: Display<MagmaState<WinitData>> = Display::new().unwrap();
@@ -68,7 +67,7 @@ pub fn init_winit() {
         backend,
         damage_tracker: damage_tracked_renderer,
     };
-    let state = MagmaState::new(event_loop.handle(), event_loop.get_signal(), &mut display, winitdata);
+    let state = MagmaState::new(event_loop.handle(), event_loop.get_signal(), &mut display, winitdata, args);
 
     let mut data = CalloopData {
         display,
@@ -77,10 +76,8 @@ pub fn init_winit() {
 
     let state = &mut data.state;
 
-    // map output to every workspace
-    for workspace in state.workspaces.iter() {
-        workspace.add_output(output.clone());
-    }
+    let config = state.config.outputs.get(&output.name());
+    state.workspaces.add_output(output.clone(), config, state.config.gaps);
 
     std::env::set_var("WAYLAND_DISPLAY", &state.socket_name);
 
@@ -144,54 +141,10 @@ pub fn winit_dispatch(
 
     winitdata.backend.bind().unwrap();
 
-    let mut renderelements: Vec<WaylandSurfaceRenderElement<_>> = vec![];
-
-    let workspace = state.workspaces.current_mut();
-    let output = workspace.outputs().next().unwrap();
-    let layer_map = layer_map_for_output(&output);
-    let (lower, upper): (Vec<&LayerSurface>, Vec<&LayerSurface>) = layer_map
-        .layers()
-        .rev()
-        .partition(|s| matches!(s.layer(), Layer::Background | Layer::Bottom));
-
-    renderelements.extend(
-        upper
-            .into_iter()
-            .filter_map(|surface| {
-                layer_map
-                    .layer_geometry(surface)
-                    .map(|geo| (geo.loc, surface))
-            })
-            .flat_map(|(loc, surface)| {
-                AsRenderElements::<GlesRenderer>::render_elements::<WaylandSurfaceRenderElement<_>>(
-                    surface,
-                    winitdata.backend.renderer(),
-                    loc.to_physical_precise_round(1),
-                    Scale::from(1.0),
-                )
-            }),
-    );
+    let workspaces = state.workspaces.rendered_on(output);
+    let renderelements: Vec<CustomRenderElements<_>> =
+        output_render_elements(winitdata.backend.renderer(), output, &workspaces);
 
-    renderelements.extend(workspace
-        .render_elements(winitdata.backend.renderer()));
-
-    renderelements.extend(
-        lower
-            .into_iter()
-            .filter_map(|surface| {
-                layer_map
-                    .layer_geometry(surface)
-                    .map(|geo| (geo.loc, surface))
-            })
-            .flat_map(|(loc, surface)| {
-                AsRenderElements::<GlesRenderer>::render_elements::<WaylandSurfaceRenderElement<_>>(
-                    surface,
-                    winitdata.backend.renderer(),
-                    loc.to_physical_precise_round(1),
-                    Scale::from(1.0),
-                )
-            }),
-    );
     winitdata
         .damage_tracker
         .render_output(
@@ -204,7 +157,7 @@ pub fn winit_dispatch(
 
     winitdata.backend.submit(Some(&[damage])).unwrap();
 
-    workspace.windows().for_each(|window| {
+    workspace.into_iter().flat_map(|w| w.windows()).for_each(|window| {
         window.send_frame(
             output,
             state.start_time.elapsed(),
@@ -213,7 +166,7 @@ pub fn winit_dispatch(
         )
     });
 
-    workspace.windows().for_each(|e| e.refresh());
+    workspace.into_iter().flat_map(|w| w.windows()).for_each(|e| e.refresh());
     state.popup_manager.cleanup();
     display.flush_clients().unwrap();
 }
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+/// Options given on the command line.
+#[derive(Debug, Default, Clone)]
+pub struct Args {
+    /// `--winit`, `--tty-udev` or `--headless`
+    pub backend: Option<String>,
+    /// Config file to use instead of searching the XDG config directories.
+    pub config: Option<PathBuf>,
+    /// Name of the wayland socket instead of the next free `wayland-N`.
+    pub socket: Option<String>,
+    /// Log filter in `RUST_LOG` syntax, takes precedence over `RUST_LOG`.
+    pub log: Option<String>,
+    pub check_config: bool,
+    pub version: bool,
+    pub print_default_config: bool,
+    pub write_default_config: bool,
+}
+
+pub static BACKENDS: &[&str] = &["--winit", "--tty-udev", "--headless"];
+
+impl Args {
+    /// Parses the arguments following the program name.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => parsed.config = Some(value(&arg, args.next())?.into()),
+                "--socket" => parsed.socket = Some(value(&arg, args.next())?),
+                "--log" => parsed.log = Some(value(&arg, args.next())?),
+                "--check-config" | "check-config" => parsed.check_config = true,
+                "--version" => parsed.version = true,
+                "--print-default-config" => parsed.print_default_config = true,
+                "--write-default-config" => parsed.write_default_config = true,
+                backend if BACKENDS.contains(&backend) => {
+                    if let Some(previous) = &parsed.backend {
+                        return Err(format!("Conflicting backends: {} and {}", previous, backend));
+                    }
+                    parsed.backend = Some(backend.to_string());
+                }
+                other => return Err(format!("Unknown option: {}", other)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value
+        .filter(|value| !value.starts_with("--"))
+        .ok_or_else(|| format!("{} needs a value", option))
+}
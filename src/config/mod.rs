@@ -1,28 +1,89 @@
-use std::{collections::HashMap, fs::OpenOptions};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
 use serde::Deserialize;
-use smithay::{output::Mode, utils::{Size, Physical}};
+use smithay::{output::Mode, utils::{Logical, Physical, Point, Size}};
+use tracing::{debug, info};
 
-use self::types::{deserialize_KeyModifiers, deserialize_Keysym, XkbConfig};
+use self::types::{deserialize_Key, deserialize_KeyModifiers, deserialize_keybindings, XkbConfig};
 
 mod types;
+
+/// The config used when there is no config file.
+pub static DEFAULT_CONFIG: &str = include_str!("../../resources/default_config.ron");
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub workspaces: u8,
+    #[serde(deserialize_with = "deserialize_keybindings")]
     pub keybindings: HashMap<KeyPattern, Action>,
+    /// Match keysym bindings against the first layout of `xkb.layout` whatever
+    /// layout is active, so `Super+q` keeps working with e.g. `layout: "us,ru"`.
+    #[serde(default)]
+    pub bind_base_layout: bool,
+    /// Bindings of multiple key patterns pressed one after another.
+    #[serde(default, deserialize_with = "deserialize_keybindings")]
+    pub sequences: HashMap<KeySequence, Action>,
+    /// Milliseconds to wait for the next key of a started sequence.
+    #[serde(default = "default_sequence_timeout")]
+    pub sequence_timeout: u64,
+    /// Bindings of pointer buttons and scroll directions.
+    #[serde(default, deserialize_with = "deserialize_keybindings")]
+    pub mousebindings: HashMap<MousePattern, Action>,
+    /// Touchpad swipes. Swipes bound to `NextWorkspace` or `PrevWorkspace`
+    /// move the workspaces along with the fingers.
+    #[serde(default, deserialize_with = "deserialize_keybindings")]
+    pub gestures: HashMap<GesturePattern, Action>,
+    /// Named sets of keybindings replacing `keybindings` and `sequences` while active.
+    #[serde(default)]
+    pub modes: HashMap<String, BindingMode>,
 
     #[serde(default = "default_gaps")]
     pub gaps: (i32, i32),
+    /// Layouts `CycleLayout` goes through, the first one is used by default.
+    #[serde(default = "default_layouts")]
+    pub layouts: Vec<LayoutKind>,
+    /// Layouts of single workspaces, by workspace id.
+    #[serde(default)]
+    pub workspace_layouts: HashMap<u8, LayoutKind>,
+    /// Number of master windows in `MasterStack` layouts.
+    #[serde(default = "default_master_count")]
+    pub master_count: usize,
+    /// Share of the width the master windows get in `MasterStack` layouts.
+    #[serde(default = "default_master_factor")]
+    pub master_factor: f32,
+    /// Share of the width new columns get in `Scrolling` layouts.
+    #[serde(default = "default_column_width")]
+    pub column_width: f32,
     #[serde(default = "default_outputs")]
     pub outputs: HashMap<String, OutputConfig>,
+    /// Settings of input devices, keyed by device name or by kind:
+    /// `"keyboard"`, `"pointer"`, `"touchpad"`, `"touch"` or `"tablet"`.
+    #[serde(default)]
+    pub input: HashMap<String, InputConfig>,
     #[serde(default = "default_autostart")]
     pub autostart: Vec<String>,
 
     pub xkb: XkbConfig,
 }
 
+/// Configuration of a single output: mode size, refresh rate in mHz,
+/// position in the output layout and the workspaces bound to the output.
+///
+/// The last two may be left out, outputs are then placed to the right
+/// of the already connected ones.
 #[derive(Debug, Deserialize, Clone)]
-pub struct OutputConfig ((i32, i32), Option<u32>);
+pub struct OutputConfig (
+    (i32, i32),
+    Option<u32>,
+    #[serde(default)] Option<(i32, i32)>,
+    #[serde(default)] Vec<u8>,
+);
 
 
 impl OutputConfig {
@@ -34,6 +95,14 @@ impl OutputConfig {
         self.1.unwrap_or(60_000)
     }
 
+    pub fn position(&self) -> Option<Point<i32, Logical>> {
+        self.2.map(Into::into)
+    }
+
+    pub fn workspaces(&self) -> &[u8] {
+        &self.3
+    }
+
     pub fn output_mode(&self) -> Mode {
         Mode {
             size: self.mode_size(),
@@ -42,32 +111,491 @@ impl OutputConfig {
     }
 }
 
+/// Delay before a held key repeats, in milliseconds, unless configured.
+pub const REPEAT_DELAY: i32 = 200;
+/// Repetitions per second of a held key, unless configured.
+pub const REPEAT_RATE: i32 = 25;
+
+/// Settings of an input device, left out ones keep the device defaults.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputConfig {
+    /// Tap to click on touchpads
+    pub tap: Option<bool>,
+    pub natural_scroll: Option<bool>,
+    pub accel_profile: Option<AccelProfile>,
+    /// Pointer acceleration between -1 and 1
+    pub accel_speed: Option<f64>,
+    pub left_handed: Option<bool>,
+    /// Disable touchpads while typing
+    pub disable_while_typing: Option<bool>,
+    /// Delay before a held key repeats, in milliseconds
+    pub repeat_delay: Option<i32>,
+    /// Repetitions per second of a held key
+    pub repeat_rate: Option<i32>,
+    /// Output touch screens and tablets are mapped to, the first output if left out
+    pub map_to_output: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AccelProfile {
+    Flat,
+    Adaptive,
+}
+
+impl InputConfig {
+    /// Takes the settings given in `other` over.
+    fn merge(&mut self, other: &InputConfig) {
+        self.tap = other.tap.or(self.tap);
+        self.natural_scroll = other.natural_scroll.or(self.natural_scroll);
+        self.accel_profile = other.accel_profile.or(self.accel_profile);
+        self.accel_speed = other.accel_speed.or(self.accel_speed);
+        self.left_handed = other.left_handed.or(self.left_handed);
+        self.disable_while_typing = other.disable_while_typing.or(self.disable_while_typing);
+        self.repeat_delay = other.repeat_delay.or(self.repeat_delay);
+        self.repeat_rate = other.repeat_rate.or(self.repeat_rate);
+        self.map_to_output = other.map_to_output.clone().or(self.map_to_output.take());
+    }
+
+    /// Key repeat delay and rate, falling back to the defaults.
+    pub fn repeat_info(&self) -> (i32, i32) {
+        (
+            self.repeat_delay.unwrap_or(REPEAT_DELAY),
+            self.repeat_rate.unwrap_or(REPEAT_RATE),
+        )
+    }
+}
+
 impl Config {
-    pub fn load() -> Config {
-        let xdg = xdg::BaseDirectories::new().ok();
-        let locations = if let Some(base) = xdg {
-            vec![
-                base.get_config_file("magma.ron"),
-                base.get_config_file("magma/config.ron"),
-            ]
+    /// Returns the first existing config file in the XDG config directories.
+    pub fn path() -> Option<PathBuf> {
+        let base = xdg::BaseDirectories::new().ok()?;
+        [
+            base.get_config_file("magma.ron"),
+            base.get_config_file("magma/config.ron"),
+        ]
+        .into_iter()
+        .inspect(|path| debug!("Trying config location: {}", path.display()))
+        .find(|path| path.exists())
+    }
+
+    /// Loads the config file, falling back to the default config if there is none.
+    pub fn load() -> Result<Config, ConfigError> {
+        match Self::path() {
+            Some(path) => Self::from_path(&path),
+            None => {
+                info!("No config file found, using the default config");
+                Ok(Self::default_config())
+            }
+        }
+    }
+
+    pub fn default_config() -> Config {
+        ron::from_str(DEFAULT_CONFIG).expect("Malformed default config")
+    }
+
+    /// Writes the default config to `magma/config.ron` in the XDG config
+    /// directory, refusing to overwrite an existing file.
+    pub fn write_default() -> Result<PathBuf, ConfigError> {
+        let path = xdg::BaseDirectories::new()
+            .map_err(|err| {
+                ConfigError::Io(PathBuf::from("magma/config.ron"), io::Error::new(io::ErrorKind::NotFound, err))
+            })?
+            .place_config_file("magma/config.ron")
+            .map_err(|err| ConfigError::Io(PathBuf::from("magma/config.ron"), err))?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(DEFAULT_CONFIG.as_bytes()))
+            .map_err(|err| ConfigError::Io(path.clone(), err))?;
+        Ok(path)
+    }
+
+    /// Reads, parses and validates the config file at `path`.
+    pub fn from_path(path: &Path) -> Result<Config, ConfigError> {
+        info!("Using config at {}", path.display());
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| ConfigError::Io(path.to_path_buf(), err))?;
+        let config: Config = ron::from_str(&contents).map_err(|error| ConfigError::Parse {
+            path: path.to_path_buf(),
+            line: contents
+                .lines()
+                .nth(error.position.line.saturating_sub(1))
+                .map(str::to_owned),
+            error,
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the values ron can't check for us, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.workspaces == 0 {
+            problems.push("`workspaces` has to be at least 1".to_string());
+        }
+        if self.gaps.0 < 0 || self.gaps.1 < 0 {
+            problems.push(format!("`gaps` can't be negative, got {:?}", self.gaps));
+        }
+
+        let mut modes: Vec<Option<&str>> = self.modes.keys().map(|name| Some(name.as_str())).collect();
+        modes.sort();
+        modes.insert(0, None);
+        for mode in modes {
+            let context = match mode {
+                Some(name) => format!("mode {}: keybinding", name),
+                None => "keybinding".to_string(),
+            };
+            let keybindings = self.keybindings(mode);
+            let sequences = self.sequences(mode);
+
+            let mut bindings: Vec<(String, &Action)> = keybindings
+                .iter()
+                .map(|(pattern, action)| (pattern.to_string(), action))
+                .chain(sequences.iter().map(|(sequence, action)| (sequence.to_string(), action)))
+                .chain(
+                    self.mousebindings(mode)
+                        .iter()
+                        .map(|(pattern, action)| (pattern.to_string(), action)),
+                )
+                .chain(
+                    // gestures don't change with the mode
+                    mode.is_none()
+                        .then_some(&self.gestures)
+                        .into_iter()
+                        .flatten()
+                        .map(|(pattern, action)| (pattern.to_string(), action)),
+                )
+                .collect();
+            bindings.sort_by(|a, b| a.0.cmp(&b.0));
+            for (pattern, action) in bindings {
+                if let Some(id) = action.workspace().filter(|id| *id >= self.workspaces) {
+                    problems.push(format!(
+                        "{} {}: {:?} refers to workspace {}, but there are only {} workspaces (0-{})",
+                        context,
+                        pattern,
+                        action,
+                        id,
+                        self.workspaces,
+                        self.workspaces.saturating_sub(1)
+                    ));
+                }
+                if let Action::EnterMode(name) = action {
+                    if !self.modes.contains_key(name) {
+                        problems.push(format!(
+                            "{} {}: mode \"{}\" is not defined in `modes`",
+                            context, pattern, name
+                        ));
+                    }
+                }
+                if let Action::RotateTree(degrees) = action {
+                    if ![90, 180, 270].contains(degrees) {
+                        problems.push(format!(
+                            "{} {}: the layout tree can only be rotated by 90, 180 or 270 degrees, got {}",
+                            context, pattern, degrees
+                        ));
+                    }
+                }
+                if let Action::Preselect(_, ratio) = action {
+                    if !(10..=90).contains(ratio) {
+                        problems.push(format!(
+                            "{} {}: a preselected split has to get between 10 and 90 percent, got {}",
+                            context, pattern, ratio
+                        ));
+                    }
+                }
+            }
+
+            let mut patterns: Vec<_> = keybindings
+                .keys()
+                .filter(|pattern| pattern.release && pattern.repeat)
+                .map(ToString::to_string)
+                .collect();
+            patterns.sort();
+            for pattern in patterns {
+                problems.push(format!(
+                    "{} {}: a binding can't both trigger on release and repeat",
+                    context, pattern
+                ));
+            }
+
+            let mut sequences: Vec<_> = sequences.keys().collect();
+            sequences.sort_by_cached_key(|sequence| sequence.to_string());
+            for sequence in sequences {
+                if sequence.0.is_empty() {
+                    problems.push(format!("{} sequences can't be empty", context));
+                } else if sequence.0.iter().any(|pattern| pattern.release || pattern.repeat) {
+                    problems.push(format!(
+                        "{} {}: keys of a sequence can't trigger on release or repeat",
+                        context, sequence
+                    ));
+                } else if let Some(pattern) = keybindings.keys().find(|pattern| sequence.0[0] == **pattern) {
+                    problems.push(format!(
+                        "{} {}: can never be completed, {} is bound on its own",
+                        context, sequence, pattern
+                    ));
+                } else if let Some(prefix) = self.sequences(mode).keys().find(|other| {
+                    other.0.len() < sequence.0.len() && sequence.0.starts_with(&other.0)
+                }) {
+                    problems.push(format!(
+                        "{} {}: can never be completed, {} is bound on its own",
+                        context, sequence, prefix
+                    ));
+                }
+            }
+        }
+
+        let mut gestures: Vec<_> = self
+            .gestures
+            .keys()
+            .filter(|pattern| pattern.fingers < 3)
+            .map(ToString::to_string)
+            .collect();
+        gestures.sort();
+        for pattern in gestures {
+            problems.push(format!(
+                "gesture {}: swipes need at least 3 fingers, fewer scroll",
+                pattern
+            ));
+        }
+
+        if self.layouts.is_empty() {
+            problems.push("`layouts` needs at least one layout".to_string());
+        }
+        let mut workspace_layouts: Vec<_> = self
+            .workspace_layouts
+            .keys()
+            .filter(|id| **id >= self.workspaces)
+            .collect();
+        workspace_layouts.sort();
+        for id in workspace_layouts {
+            problems.push(format!(
+                "workspace_layouts: workspace {} does not exist, there are only {} workspaces",
+                id, self.workspaces
+            ));
+        }
+        if !(0.1..=0.9).contains(&self.master_factor) {
+            problems.push(format!(
+                "`master_factor` has to be between 0.1 and 0.9, got {}",
+                self.master_factor
+            ));
+        }
+        if !(0.1..=1.0).contains(&self.column_width) {
+            problems.push(format!(
+                "`column_width` has to be between 0.1 and 1, got {}",
+                self.column_width
+            ));
+        }
+
+        let mut inputs: Vec<_> = self.input.iter().collect();
+        inputs.sort_by_key(|(name, _)| name.as_str());
+        for (name, input) in inputs {
+            if let Some(speed) = input.accel_speed.filter(|speed| !(-1.0..=1.0).contains(speed)) {
+                problems.push(format!(
+                    "input {}: `accel_speed` has to be between -1 and 1, got {}",
+                    name, speed
+                ));
+            }
+            if let Some(delay) = input.repeat_delay.filter(|delay| *delay <= 0) {
+                problems.push(format!("input {}: `repeat_delay` has to be positive, got {}", name, delay));
+            }
+            if let Some(rate) = input.repeat_rate.filter(|rate| *rate < 0) {
+                problems.push(format!("input {}: `repeat_rate` can't be negative, got {}", name, rate));
+            }
+        }
+
+        let mut outputs: Vec<_> = self.outputs.iter().collect();
+        outputs.sort_by_key(|(name, _)| name.as_str());
+        for (name, output) in outputs {
+            let size = output.mode_size();
+            if size.w <= 0 || size.h <= 0 {
+                problems.push(format!(
+                    "output {}: mode size has to be positive, got {}x{}",
+                    name, size.w, size.h
+                ));
+            }
+            if output.mode_refresh() == 0 {
+                problems.push(format!("output {}: refresh rate can't be 0", name));
+            }
+            for id in output.workspaces().iter().filter(|id| **id >= self.workspaces) {
+                problems.push(format!(
+                    "output {}: workspace {} does not exist, there are only {} workspaces",
+                    name, id, self.workspaces
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
         } else {
-            vec![]
-        };
-
-        for path in locations {
-            dbg!("Trying config location: {}", path.display());
-            if path.exists() {
-                dbg!("Using config at {}", path.display());
-                return ron::de::from_reader(OpenOptions::new().read(true).open(path).unwrap())
-                    .expect("Malformed config file");
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// The layout workspace `id` starts with.
+    pub fn workspace_layout(&self, id: u8) -> LayoutKind {
+        self.workspace_layouts
+            .get(&id)
+            .or_else(|| self.layouts.first())
+            .copied()
+            .unwrap_or(LayoutKind::Bsp)
+    }
+
+    /// The keybindings of `mode`, or the default ones if no mode is active.
+    pub fn keybindings(&self, mode: Option<&str>) -> &HashMap<KeyPattern, Action> {
+        mode.and_then(|mode| self.modes.get(mode))
+            .map_or(&self.keybindings, |mode| &mode.keybindings)
+    }
+
+    /// The key sequences of `mode`, or the default ones if no mode is active.
+    pub fn sequences(&self, mode: Option<&str>) -> &HashMap<KeySequence, Action> {
+        mode.and_then(|mode| self.modes.get(mode))
+            .map_or(&self.sequences, |mode| &mode.sequences)
+    }
+
+    /// Settings of the input device `name` of the given `kinds`, most specific
+    /// kind first. Entries of more specific kinds and of the name win.
+    pub fn input_config(&self, name: &str, kinds: &[&str]) -> InputConfig {
+        let mut config = self.input_kind_config(kinds);
+        if let Some(entry) = self.input.get(name) {
+            config.merge(entry);
+        }
+        config
+    }
+
+    /// Settings shared by input devices of the given `kinds`, most specific
+    /// kind first, without the entries of any single device.
+    pub fn input_kind_config(&self, kinds: &[&str]) -> InputConfig {
+        let mut config = InputConfig::default();
+        for kind in kinds.iter().rev() {
+            if let Some(entry) = self.input.get(*kind) {
+                config.merge(entry);
+            }
+        }
+        config
+    }
+
+    /// The pointer bindings of `mode`, or the default ones if no mode is active.
+    pub fn mousebindings(&self, mode: Option<&str>) -> &HashMap<MousePattern, Action> {
+        mode.and_then(|mode| self.modes.get(mode))
+            .map_or(&self.mousebindings, |mode| &mode.mousebindings)
+    }
+
+    /// Names of configured outputs that are not among `connected`.
+    pub fn unknown_outputs<'a>(&'a self, connected: &[String]) -> Vec<&'a str> {
+        let mut unknown: Vec<_> = self
+            .outputs
+            .keys()
+            .filter(|name| !connected.contains(name))
+            .map(String::as_str)
+            .collect();
+        unknown.sort();
+        unknown
+    }
+
+    /// Output names that only one part of the config mentions: `map_to_output`
+    /// targets without an entry in `outputs`, likely misspelled.
+    pub fn unmatched_outputs(&self) -> Vec<String> {
+        let mut unmatched: Vec<_> = self
+            .input
+            .iter()
+            .filter_map(|(input, config)| {
+                let output = config.map_to_output.as_ref()?;
+                (!self.outputs.contains_key(output)).then(|| {
+                    format!("input {}: output {} has no entry in `outputs`", input, output)
+                })
+            })
+            .collect();
+        unmatched.sort();
+        unmatched
+    }
+}
+
+/// Why a config file could not be used.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    /// The file is no valid RON or doesn't match the config's structure,
+    /// `line` is the source line the error points to.
+    Parse {
+        path: PathBuf,
+        error: ron::error::SpannedError,
+        line: Option<String>,
+    },
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "Failed to access {}: {}", path.display(), err),
+            ConfigError::Parse { path, error, line } => {
+                write!(
+                    f,
+                    "Malformed config file {}:{}:{}: {}",
+                    path.display(),
+                    error.position.line,
+                    error.position.col,
+                    error.code
+                )?;
+                if let Some(line) = line {
+                    write!(f, "\n{:>5} | {}", error.position.line, line)?;
+                    write!(f, "\n      | {:>col$}", "^", col = error.position.col.max(1))?;
+                }
+                Ok(())
+            }
+            ConfigError::Invalid(problems) => {
+                write!(f, "Invalid config:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
             }
         }
-        panic!("No config file found")
     }
 }
+
+impl std::error::Error for ConfigError {}
+
+/// Key patterns pressed one after another, like `Super+w, h`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Hash)]
+#[serde(transparent)]
+pub struct KeySequence(pub Vec<KeyPattern>);
+
+/// A keybinding mode, entered with `EnterMode(name)` and left with `ExitMode`.
+///
+/// Keys not bound in the mode are forwarded to clients, so a mode that only
+/// binds `ExitMode` passes everything else through.
+#[derive(Debug, Deserialize)]
+pub struct BindingMode {
+    #[serde(deserialize_with = "deserialize_keybindings")]
+    pub keybindings: HashMap<KeyPattern, Action>,
+    #[serde(default, deserialize_with = "deserialize_keybindings")]
+    pub sequences: HashMap<KeySequence, Action>,
+    #[serde(default, deserialize_with = "deserialize_keybindings")]
+    pub mousebindings: HashMap<MousePattern, Action>,
+}
+
 fn default_gaps() -> (i32, i32) {
     (5, 5)
 }
+fn default_layouts() -> Vec<LayoutKind> {
+    vec![LayoutKind::Bsp]
+}
+fn default_master_count() -> usize {
+    1
+}
+fn default_master_factor() -> f32 {
+    0.55
+}
+fn default_column_width() -> f32 {
+    0.5
+}
+fn default_sequence_timeout() -> u64 {
+    1000
+}
 fn default_autostart() -> Vec<String> {
     vec![]
 }
@@ -100,8 +628,61 @@ pub struct KeyPattern {
     #[serde(deserialize_with = "deserialize_KeyModifiers")]
     pub modifiers: KeyModifiers,
     /// The actual key, that was pressed
-    #[serde(deserialize_with = "deserialize_Keysym")]
-    pub key: u32,
+    #[serde(deserialize_with = "deserialize_Key")]
+    pub key: Key,
+    /// Trigger when the key is released instead of pressed, and only if no other
+    /// key was pressed since. `modifiers` are the ones still held after the release,
+    /// so tapping Super alone is `(modifiers: [], key: "Super_L", release: true)`.
+    #[serde(default)]
+    pub release: bool,
+    /// Trigger again at the keyboard repeat rate while the key is held.
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+/// The key of a [`KeyPattern`], a keysym name like `"q"` or a keycode like `Code(24)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// Matched against the keysyms the key produces without modifiers.
+    Sym(u32),
+    /// An xkb keycode (the evdev code plus 8), matched whatever the layout.
+    Code(u32),
+}
+
+/// A pointer button or scroll direction combined with modifiers, like `Super+Button1`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct MousePattern {
+    #[serde(deserialize_with = "deserialize_KeyModifiers")]
+    pub modifiers: KeyModifiers,
+    pub button: MouseButton,
+}
+
+/// Pointer buttons, numbered like X11 does, and scroll directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Hash)]
+pub enum MouseButton {
+    /// Left button
+    Button1,
+    /// Middle button
+    Button2,
+    /// Right button
+    Button3,
+    /// Back side button
+    Button8,
+    /// Forward side button
+    Button9,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+/// A touchpad swipe with a number of fingers, like three fingers to the left.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct GesturePattern {
+    pub fingers: u32,
+    pub direction: Direction,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
@@ -110,9 +691,107 @@ pub enum Action {
     Debug,
     Close,
     Workspace(u8),
+    /// Switches to the next workspace not shown on another output.
+    NextWorkspace,
+    /// Switches to the previous workspace not shown on another output.
+    PrevWorkspace,
     MoveWindowToWorkspace(u8),
     MoveWindowAndSwitchToWorkspace(u8),
     ToggleWindowFloating,
     VTSwitch(i32),
     Spawn(String),
+    FocusDirection(Direction),
+    FocusNext,
+    FocusPrev,
+    SwapWindow(Direction),
+    MoveWindow(Direction),
+    ResizeWindow { direction: Direction, amount: i32 },
+    ToggleFullscreen,
+    ToggleMaximize,
+    FocusOutput(Direction),
+    MoveWorkspaceToOutput(Direction),
+    ReloadConfig,
+    EnterMode(String),
+    ExitMode,
+    /// Switches the current workspace to another layout.
+    SetLayout(LayoutKind),
+    /// Switches the current workspace to the next layout of `layouts`.
+    CycleLayout,
+    /// Adds master windows to a `MasterStack` layout, or removes them if negative.
+    ChangeMasterCount(i32),
+    /// Grows the master area of a `MasterStack` layout by a percentage of the width.
+    ChangeMasterFactor(i32),
+    /// Widens the focused column of a `Scrolling` layout by a percentage of the width,
+    /// or narrows it if negative.
+    ChangeColumnWidth(i32),
+    /// Splits the focused window in a direction for the next window, which gets
+    /// the given percentage of its area.
+    Preselect(Direction, i32),
+    CancelPreselect,
+    /// Sets the splits of the layout tree so every window gets the same area.
+    BalanceTree,
+    /// Rotates the layout tree clockwise by 90, 180 or 270 degrees.
+    RotateTree(u16),
+    /// Mirrors the layout tree.
+    FlipTree(Flip),
+    /// Splits every area of the layout tree in half.
+    EqualizeSplits,
+    /// Moves the focused window into a tab group with the window in a direction.
+    GroupWith(Direction),
+    /// Takes the focused window out of its tab group.
+    Ungroup,
+    /// Focuses the next tab of the focused group.
+    NextTab,
+    /// Focuses the previous tab of the focused group.
+    PrevTab,
+    /// Switches the focused group between a tabbed and a stacked tab bar.
+    ToggleGroupKind,
+}
+
+/// The ways `FlipTree` mirrors the layout tree.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Flip {
+    /// Left and right change places.
+    Horizontal,
+    /// Top and bottom change places.
+    Vertical,
+}
+
+/// The ways to arrange tiled windows, see [`crate::utils::tiling::Layout`].
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayoutKind {
+    /// Windows split the area of the window they open next to, alternating
+    /// between horizontal and vertical splits.
+    Bsp,
+    /// Master windows on the left, the others stacked on the right.
+    MasterStack,
+    /// Only the focused window, taking up the whole area.
+    Monocle,
+    /// Columns of equal width.
+    Columns,
+    /// Rows and columns of equal size.
+    Grid,
+    /// A column per window on a strip wider than the output, scrolled to
+    /// keep the focused column in view.
+    Scrolling,
+}
+
+impl Action {
+    /// The workspace the action refers to, if any.
+    pub fn workspace(&self) -> Option<u8> {
+        match self {
+            Action::Workspace(id)
+            | Action::MoveWindowToWorkspace(id)
+            | Action::MoveWindowAndSwitchToWorkspace(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
 }
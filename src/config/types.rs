@@ -1,7 +1,13 @@
-use serde::Deserialize;
+use std::{collections::HashMap, fmt, hash::Hash, marker::PhantomData};
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize,
+};
 use smithay::input::keyboard::{keysyms as KeySyms, xkb, Keysym, ModifiersState, XkbConfig as WlXkbConfig};
+use tracing::warn;
 
-use super::{KeyModifier, KeyModifiers};
+use super::{Action, GesturePattern, Key, KeyModifier, KeyModifiers, KeyPattern, KeySequence, MousePattern};
 
 #[derive(Deserialize)]
 #[serde(transparent)]
@@ -32,23 +38,17 @@ where
     KeyModifiersDef::deserialize(deserializer).map(Into::into)
 }
 
-#[allow(non_snake_case)]
-pub fn deserialize_Keysym<'de, D>(deserializer: D) -> Result<Keysym, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::{Error, Unexpected};
-
-    let name = String::deserialize(deserializer)?;
-    //let name = format!("KEY_{}", code);
-    match xkb::keysym_from_name(&name, xkb::KEYSYM_NO_FLAGS) {
-        KeySyms::KEY_NoSymbol => match xkb::keysym_from_name(&name, xkb::KEYSYM_CASE_INSENSITIVE) {
-            KeySyms::KEY_NoSymbol => Err(<D::Error as Error>::invalid_value(
-                Unexpected::Str(&name),
+fn keysym_from_name<E: serde::de::Error>(name: &str) -> Result<Keysym, E> {
+    use serde::de::Unexpected;
+
+    match xkb::keysym_from_name(name, xkb::KEYSYM_NO_FLAGS) {
+        KeySyms::KEY_NoSymbol => match xkb::keysym_from_name(name, xkb::KEYSYM_CASE_INSENSITIVE) {
+            KeySyms::KEY_NoSymbol => Err(E::invalid_value(
+                Unexpected::Str(name),
                 &"One of the keysym names of xkbcommon.h without the 'KEY_' prefix",
             )),
             x => {
-                dbg!(
+                warn!(
                     "Key-Binding '{}' only matched case insensitive for {:?}",
                     name,
                     xkb::keysym_get_name(x)
@@ -60,6 +60,144 @@ where
     }
 }
 
+/// Deserializes a keysym name like `"q"`, or a keycode written as `Code(24)`.
+#[allow(non_snake_case)]
+pub fn deserialize_Key<'de, D>(deserializer: D) -> Result<Key, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct KeyVisitor;
+
+    impl<'de> Visitor<'de> for KeyVisitor {
+        type Value = Key;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a keysym name like \"q\" or a keycode like Code(24)")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Key, E> {
+            keysym_from_name(name).map(Key::Sym)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Key, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            // ron hands `Code(24)` over as a tuple, dropping the name
+            let code = seq
+                .next_element::<u32>()?
+                .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+            if seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(serde::de::Error::invalid_length(2, &self));
+            }
+            Ok(Key::Code(code))
+        }
+    }
+
+    deserializer.deserialize_any(KeyVisitor)
+}
+
+/// Deserializes a map of bindings, rejecting keys bound twice instead of
+/// silently keeping the last binding.
+pub fn deserialize_keybindings<'de, D, K>(deserializer: D) -> Result<HashMap<K, Action>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    K: Deserialize<'de> + Eq + Hash + fmt::Display,
+{
+    struct KeybindingsVisitor<K>(PhantomData<K>);
+
+    impl<'de, K> Visitor<'de> for KeybindingsVisitor<K>
+    where
+        K: Deserialize<'de> + Eq + Hash + fmt::Display,
+    {
+        type Value = HashMap<K, Action>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of key patterns to actions")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut keybindings = HashMap::new();
+            while let Some((pattern, action)) = map.next_entry::<K, Action>()? {
+                if let Some(previous) = keybindings.get(&pattern) {
+                    return Err(serde::de::Error::custom(format!(
+                        "{} is bound twice, to {:?} and {:?}",
+                        pattern, previous, action
+                    )));
+                }
+                keybindings.insert(pattern, action);
+            }
+            Ok(keybindings)
+        }
+    }
+
+    deserializer.deserialize_map(KeybindingsVisitor(PhantomData))
+}
+
+/// Writes the pressed modifiers as a `Super+Ctrl+` prefix.
+impl fmt::Display for KeyModifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.logo, "Super"),
+            (self.ctrl, "Ctrl"),
+            (self.alt, "Alt"),
+            (self.shift, "Shift"),
+        ];
+        for (_, name) in modifiers.iter().filter(|(pressed, _)| *pressed) {
+            write!(f, "{}+", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.modifiers, self.key)?;
+        match (self.release, self.repeat) {
+            (true, true) => write!(f, " (release, repeat)"),
+            (true, false) => write!(f, " (release)"),
+            (false, true) => write!(f, " (repeat)"),
+            (false, false) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Sym(sym) => write!(f, "{}", xkb::keysym_get_name(*sym)),
+            Key::Code(code) => write!(f, "Code({})", code),
+        }
+    }
+}
+
+impl fmt::Display for GesturePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-finger swipe {:?}", self.fingers, self.direction)
+    }
+}
+
+impl fmt::Display for MousePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{:?}", self.modifiers, self.button)
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, pattern) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", pattern)?;
+        }
+        Ok(())
+    }
+}
+
 impl std::ops::AddAssign<KeyModifier> for KeyModifiers {
     fn add_assign(&mut self, rhs: KeyModifier) {
         match rhs {
@@ -80,7 +218,24 @@ impl PartialEq<ModifiersState> for KeyModifiers {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl Key {
+    /// Whether the key with xkb keycode `code`, producing `syms`, is this key.
+    pub fn matches(&self, code: u32, syms: &[Keysym]) -> bool {
+        match self {
+            Key::Sym(sym) => syms.contains(sym),
+            Key::Code(key_code) => *key_code == code,
+        }
+    }
+}
+
+impl KeyPattern {
+    /// Whether a key press with `modifiers` active produces this pattern.
+    pub fn matches(&self, modifiers: &ModifiersState, code: u32, syms: &[Keysym]) -> bool {
+        self.modifiers == *modifiers && self.key.matches(code, syms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 pub struct XkbConfig {
     pub rules: String,
     pub model: String,
@@ -101,6 +256,22 @@ impl Default for XkbConfig {
     }
 }
 
+impl XkbConfig {
+    /// Compiles the keymap described by this config on its own, without a keyboard.
+    pub fn keymap(&self) -> Option<xkb::Keymap> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        xkb::Keymap::new_from_names(
+            &context,
+            &self.rules,
+            &self.model,
+            &self.layout,
+            &self.variant,
+            self.options.clone(),
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+    }
+}
+
 impl<'a> Into<WlXkbConfig<'a>> for &'a XkbConfig {
     fn into(self) -> WlXkbConfig<'a> {
         WlXkbConfig {
@@ -25,12 +25,24 @@ impl<BackendData: Backend> CompositorHandler for MagmaState<BackendData> {
             while let Some(parent) = get_parent(&root) {
                 root = parent;
             }
-            if let Some(window) = self
+            if let Some(index) = self
+                .pending_windows
+                .iter()
+                .position(|p| p.window.toplevel().wl_surface() == &root)
+            {
+                let pending = self.pending_windows.remove(index);
+                pending.window.on_commit();
+                self.map_toplevel(pending);
+            } else if let Some(window) = self
                 .workspaces
                 .all_windows()
                 .find(|w| w.toplevel().wl_surface() == &root)
+                .map(|w| w.clone())
             {
                 window.on_commit();
+                if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+                    workspace.update_floating_size(&window);
+                }
             }
         };
         self.popup_manager.commit(surface);
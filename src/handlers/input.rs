@@ -1,8 +1,9 @@
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
     config::Action,
     state::{Backend, MagmaState},
+    utils::{focus::FocusTarget, tiling::{new_layout, update_layout}},
 };
 
 impl<BackendData: Backend> MagmaState<BackendData> {
@@ -11,28 +12,31 @@ impl<BackendData: Backend> MagmaState<BackendData> {
             Action::Quit => self.loop_signal.stop(),
             Action::Debug => todo!(),
             Action::Close => {
-                if let Some(d) = self
-                    .workspaces
-                    .current()
-                    .window_under(self.pointer_location)
-                {
-                    d.0.toplevel().send_close()
+                if let Some(window) = self.workspaces.current().focused() {
+                    window.toplevel().send_close()
                 }
             }
             Action::Workspace(id) => {
-            self.workspaces.activate(id, &mut self.ipc_manager);
-            self.set_input_focus_auto();
+            self.workspaces.activate(id, &mut self.ipc_manager, self.config.gaps);
+            self.refocus();
             },
-            Action::MoveWindowToWorkspace(id) => {
-                let window = self
+            Action::NextWorkspace | Action::PrevWorkspace => {
+                let forward = matches!(action, Action::NextWorkspace);
+                let neighbour = self
                     .workspaces
-                    .current()
-                    .window_under(self.pointer_location)
-                    .map(|d| d.0.clone());
+                    .current_output()
+                    .and_then(|output| self.workspaces.neighbour(output, forward));
+                if let Some(id) = neighbour {
+                    self.handle_action(Action::Workspace(id));
+                }
+            }
+            Action::MoveWindowToWorkspace(id) => {
+                let window = self.workspaces.current().focused().cloned();
 
                 if let Some(window) = window {
                     self.workspaces
                         .move_window_to_workspace(&window, id, self.config.gaps);
+                    self.refocus();
                 }
                 self.ipc_manager.update_occupied_workspaces(&mut self.workspaces);
             }
@@ -40,7 +44,17 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                 self.handle_action(Action::MoveWindowToWorkspace(u8));
                 self.handle_action(Action::Workspace(u8));
             }
-            Action::ToggleWindowFloating => todo!(),
+            Action::ToggleWindowFloating => {
+                let workspace = self.workspaces.current_mut();
+                if let Some(focused) = workspace.focused().cloned() {
+                    if workspace.is_floating(&focused) {
+                        workspace.tile_window(&focused);
+                    } else {
+                        workspace.float_window(&focused, None);
+                    }
+                    update_layout(workspace, self.config.gaps);
+                }
+            }
             Action::Spawn(command) => {
                 if let Err(err) = std::process::Command::new("/bin/sh")
                     .arg("-c")
@@ -51,6 +65,185 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                 }
             }
             Action::VTSwitch(_) => {info!("VTSwitch is not used in Winit backend.")},
+            Action::FocusDirection(direction) => {
+                if let Some(window) = self.workspaces.current().window_in_direction(direction) {
+                    self.set_input_focus(FocusTarget::Window(window));
+                }
+            }
+            Action::FocusNext => {
+                if let Some(window) = self.workspaces.current().cycle_window(true) {
+                    self.set_input_focus(FocusTarget::Window(window));
+                }
+            }
+            Action::FocusPrev => {
+                if let Some(window) = self.workspaces.current().cycle_window(false) {
+                    self.set_input_focus(FocusTarget::Window(window));
+                }
+            }
+            Action::SwapWindow(direction) => {
+                let workspace = self.workspaces.current_mut();
+                if let (Some(focused), Some(neighbour)) = (
+                    workspace.focused().cloned(),
+                    workspace.window_in_direction(direction),
+                ) {
+                    workspace.swap_windows(&focused, &neighbour);
+                    update_layout(workspace, self.config.gaps);
+                }
+            }
+            Action::MoveWindow(direction) => {
+                let workspace = self.workspaces.current_mut();
+                if let (Some(focused), Some(neighbour)) = (
+                    workspace.focused().cloned(),
+                    workspace.window_in_direction(direction),
+                ) {
+                    workspace.move_window(&focused, &neighbour, direction);
+                    update_layout(workspace, self.config.gaps);
+                }
+            }
+            Action::ToggleFullscreen => {
+                if let Some(focused) = self.workspaces.current().focused().cloned() {
+                    let fullscreen = self.workspaces.current().is_fullscreen(&focused);
+                    self.workspaces
+                        .set_fullscreen(&focused, !fullscreen, self.config.gaps);
+                }
+            }
+            Action::ToggleMaximize => {
+                if let Some(focused) = self.workspaces.current().focused().cloned() {
+                    let maximized = self.workspaces.current().is_maximized(&focused);
+                    self.workspaces
+                        .set_maximized(&focused, !maximized, self.config.gaps);
+                }
+            }
+            Action::FocusOutput(direction) => {
+                if let Some(output) = self.workspaces.output_in_direction(direction) {
+                    self.workspaces.focus_output(&output, &mut self.ipc_manager);
+                    self.warp_pointer_to_output(&output);
+                    self.refocus();
+                }
+            }
+            Action::MoveWorkspaceToOutput(direction) => {
+                if let Some(output) = self.workspaces.output_in_direction(direction) {
+                    self.workspaces
+                        .move_current_to_output(direction, self.config.gaps);
+                    self.warp_pointer_to_output(&output);
+                    self.refocus();
+                }
+            }
+            Action::ReloadConfig => self.reload_config(),
+            Action::EnterMode(mode) => self.set_mode(Some(mode)),
+            Action::ExitMode => self.set_mode(None),
+            Action::ResizeWindow { direction, amount } => {
+                let workspace = self.workspaces.current_mut();
+                if let Some(focused) = workspace.focused().cloned() {
+                    workspace.resize_window(&focused, direction, amount as f32, self.config.gaps);
+                }
+            }
+            Action::SetLayout(kind) => {
+                let workspace = self.workspaces.current_mut();
+                workspace.set_layout(new_layout(kind, &self.config));
+                update_layout(workspace, self.config.gaps);
+            }
+            Action::CycleLayout => {
+                let layouts = &self.config.layouts;
+                let current = self.workspaces.current().layout().kind();
+                let next = layouts
+                    .iter()
+                    .position(|kind| *kind == current)
+                    .map_or(0, |i| (i + 1) % layouts.len());
+                if let Some(kind) = layouts.get(next).copied() {
+                    self.handle_action(Action::SetLayout(kind));
+                }
+            }
+            Action::ChangeMasterCount(delta) => {
+                let workspace = self.workspaces.current_mut();
+                if workspace.layout_mut().change_master_count(delta) {
+                    update_layout(workspace, self.config.gaps);
+                }
+            }
+            Action::ChangeMasterFactor(percent) => {
+                let workspace = self.workspaces.current_mut();
+                if workspace.layout_mut().change_master_factor(percent as f32 / 100.0) {
+                    update_layout(workspace, self.config.gaps);
+                }
+            }
+            Action::ChangeColumnWidth(percent) => {
+                let workspace = self.workspaces.current_mut();
+                if let Some(focused) = workspace.focused().cloned() {
+                    if workspace
+                        .layout_mut()
+                        .change_column_width(&focused, percent as f32 / 100.0)
+                    {
+                        update_layout(workspace, self.config.gaps);
+                    }
+                }
+            }
+            Action::Preselect(direction, percent) => {
+                self.workspaces
+                    .current_mut()
+                    .preselect(direction, percent as f32 / 100.0);
+            }
+            Action::CancelPreselect => self.workspaces.current_mut().cancel_preselect(),
+            Action::BalanceTree
+            | Action::RotateTree(_)
+            | Action::FlipTree(_)
+            | Action::EqualizeSplits => {
+                let workspace = self.workspaces.current_mut();
+                let tree = &mut workspace.layout_tree;
+                match action {
+                    Action::BalanceTree => tree.balance(),
+                    Action::RotateTree(degrees) => tree.rotate(degrees),
+                    Action::FlipTree(flip) => tree.flip(flip),
+                    _ => tree.equalize(),
+                }
+                update_layout(workspace, self.config.gaps);
+            }
+            Action::GroupWith(direction) => {
+                let workspace = self.workspaces.current_mut();
+                if let (Some(focused), Some(neighbour)) = (
+                    workspace.focused().cloned(),
+                    workspace.window_in_direction(direction),
+                ) {
+                    if workspace.group_windows(&focused, &neighbour) {
+                        update_layout(workspace, self.config.gaps);
+                    }
+                }
+            }
+            Action::Ungroup => {
+                let workspace = self.workspaces.current_mut();
+                if let Some(focused) = workspace.focused().cloned() {
+                    if workspace.ungroup_window(&focused) {
+                        update_layout(workspace, self.config.gaps);
+                    }
+                }
+            }
+            Action::NextTab | Action::PrevTab => {
+                let forward = matches!(action, Action::NextTab);
+                if let Some(window) = self.workspaces.current().cycle_tab(forward) {
+                    self.set_input_focus(FocusTarget::Window(window));
+                }
+            }
+            Action::ToggleGroupKind => {
+                let workspace = self.workspaces.current_mut();
+                if let Some(focused) = workspace.focused().cloned() {
+                    if workspace.toggle_group_kind(&focused) {
+                        update_layout(workspace, self.config.gaps);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switches to the keybindings of `mode`, `None` being the default ones.
+    pub fn set_mode(&mut self, mode: Option<String>) {
+        if let Some(name) = &mode {
+            if !self.config.modes.contains_key(name) {
+                warn!("Unknown keybinding mode \"{}\"", name);
+                return;
+            }
         }
+        info!("Entering keybinding mode {}", mode.as_deref().unwrap_or("default"));
+        self.cancel_key_sequence();
+        self.mode = mode;
+        self.ipc_manager.update_active_mode(self.mode.as_deref());
     }
 }
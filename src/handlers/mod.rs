@@ -18,10 +18,11 @@ use smithay::wayland::data_device::{
 use smithay::wayland::primary_selection::{PrimarySelectionHandler, set_primary_focus};
 use smithay::wayland::seat::WaylandFocus;
 use smithay::wayland::shell::wlr_layer::{WlrLayerShellHandler, WlrLayerShellState, LayerSurface as WlrLayerSurface, Layer};
-use smithay::{delegate_data_device, delegate_output, delegate_seat, delegate_layer_shell, delegate_primary_selection};
+use smithay::{delegate_data_device, delegate_output, delegate_seat, delegate_layer_shell, delegate_pointer_gestures, delegate_primary_selection, delegate_tablet_manager};
 
 use crate::state::{Backend, MagmaState};
 use crate::utils::focus::FocusTarget;
+use crate::utils::tiling::update_layout;
 
 impl<BackendData: Backend> SeatHandler for MagmaState<BackendData> {
     type KeyboardFocus = FocusTarget;
@@ -49,6 +50,13 @@ impl<BackendData: Backend> SeatHandler for MagmaState<BackendData> {
         if let Some(focus_target) = focused {
             match focus_target {
                 FocusTarget::Window(w) => {
+                    if let Some(workspace) = self.workspaces.workspace_from_window(w) {
+                        workspace.set_focused(w.clone());
+                        workspace.raise_window(w);
+                        if workspace.layout().follows_focus() {
+                            update_layout(workspace, self.config.gaps);
+                        }
+                    }
                     for window in self.workspaces.all_windows(){
                         if window.eq(w){
                             window.set_activated(true);
@@ -101,6 +109,18 @@ delegate_primary_selection!(@<BackendData: Backend + 'static> MagmaState<Backend
 
 delegate_output!(@<BackendData: Backend + 'static> MagmaState<BackendData>);
 
+//
+// Pointer Gestures
+//
+
+delegate_pointer_gestures!(@<BackendData: Backend + 'static> MagmaState<BackendData>);
+
+//
+// Tablet Manager
+//
+
+delegate_tablet_manager!(@<BackendData: Backend + 'static> MagmaState<BackendData>);
+
 impl<BackendData: Backend> WlrLayerShellHandler for MagmaState<BackendData>{
     fn shell_state(&mut self) -> &mut WlrLayerShellState {
         &mut self.layer_shell_state
@@ -114,7 +134,11 @@ impl<BackendData: Backend> WlrLayerShellHandler for MagmaState<BackendData>{
         namespace: String,
     ) {
         let output = output.as_ref().and_then(Output::from_resource).unwrap_or_else(|| {
-            self.workspaces.current().outputs().next().unwrap().clone()
+            self.workspaces
+                .current_output()
+                .or_else(|| self.workspaces.outputs().next())
+                .unwrap()
+                .clone()
         });
         let mut map = layer_map_for_output(&output);
         let layer_surface = LayerSurface::new(surface, namespace);
@@ -133,7 +157,7 @@ impl<BackendData: Backend> WlrLayerShellHandler for MagmaState<BackendData>{
         }) {
             map.unmap_layer(&layer);
         }
-        self.set_input_focus_auto()
+        self.refocus()
     }
 }
 
@@ -3,15 +3,26 @@ use std::sync::Mutex;
 use smithay::{
     delegate_xdg_decoration, delegate_xdg_shell,
     desktop::{Window, PopupKind, PopupManager, layer_map_for_output, WindowSurfaceType},
+    input::{
+        pointer::{Focus, GrabStartData as PointerGrabStartData},
+        Seat,
+    },
     reexports::{
-        wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
-        wayland_server::protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
+        wayland_protocols::xdg::{
+            decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
+            shell::server::xdg_toplevel::ResizeEdge,
+        },
+        wayland_server::{
+            protocol::{wl_output::WlOutput, wl_seat::WlSeat, wl_surface::WlSurface},
+            Resource,
+        },
     },
-    utils::Serial,
+    utils::{Logical, Serial, Size},
     wayland::{
         compositor::with_states,
+        seat::WaylandFocus,
         shell::{xdg::{
-            decoration::XdgDecorationHandler, PopupSurface, PositionerState, ToplevelSurface,
+            decoration::XdgDecorationHandler, PopupSurface, PositionerState, SurfaceCachedState, ToplevelSurface,
             XdgShellHandler, XdgShellState, XdgToplevelSurfaceRoleAttributes, XdgPopupSurfaceData,
         }, wlr_layer::LayerSurfaceData},
     },
@@ -21,28 +32,41 @@ use tracing::warn;
 use crate::{
     state::{Backend, MagmaState},
     utils::{
-        tiling::{bsp_layout, WindowLayoutEvent},
+        tiling::{layout_window, WindowLayoutEvent},
         workspaces::Workspaces, focus::FocusTarget,
+        grabs::{MoveSurfaceGrab, ResizeSurfaceGrab, ResizeTileGrab},
     },
 };
 
+/// A toplevel waiting for its initial commit, with the states it asked for until then.
+pub struct PendingWindow {
+    pub window: Window,
+    pub fullscreen: bool,
+    pub maximized: bool,
+}
+
 impl<BackendData: Backend> XdgShellHandler for MagmaState<BackendData> {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
         &mut self.xdg_shell_state
     }
 
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
-        let window = Window::new(surface);
-        bsp_layout(
-            self.workspaces.current_mut(),
-            window.clone(),
-            WindowLayoutEvent::Added,
-            self.config.gaps,
-        );
-        self.set_input_focus(FocusTarget::Window(window));
-        self.ipc_manager.update_occupied_workspaces(&mut self.workspaces);
+        // parent and size limits are only known after the initial commit, see `map_toplevel`
+        self.pending_windows.push(PendingWindow {
+            window: Window::new(surface),
+            fullscreen: false,
+            maximized: false,
+        });
     }
     fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
+        if let Some(index) = self
+            .pending_windows
+            .iter()
+            .position(|p| p.window.toplevel() == &surface)
+        {
+            self.pending_windows.remove(index);
+            return;
+        }
         let window = self
             .workspaces
             .all_windows()
@@ -51,14 +75,14 @@ impl<BackendData: Backend> XdgShellHandler for MagmaState<BackendData> {
             .clone();
 
         let workspace = self.workspaces.workspace_from_window(&window).unwrap();
-        bsp_layout(
+        layout_window(
             workspace,
             window,
             WindowLayoutEvent::Removed,
             self.config.gaps,
         );
 
-        self.set_input_focus_auto();
+        self.refocus();
         self.ipc_manager.update_occupied_workspaces(&mut self.workspaces);
     }
     fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) {
@@ -74,10 +98,160 @@ impl<BackendData: Backend> XdgShellHandler for MagmaState<BackendData> {
     fn grab(&mut self, _surface: PopupSurface, _seat: WlSeat, _serial: Serial) {
         // TODO
     }
+
+    fn fullscreen_request(&mut self, surface: ToplevelSurface, _output: Option<WlOutput>) {
+        if let Some(window) = self.window_for_toplevel(&surface) {
+            self.workspaces.set_fullscreen(&window, true, self.config.gaps);
+        } else if let Some(pending) = self.pending_window(&surface) {
+            pending.fullscreen = true;
+        }
+    }
+
+    fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_toplevel(&surface) {
+            self.workspaces.set_fullscreen(&window, false, self.config.gaps);
+        } else if let Some(pending) = self.pending_window(&surface) {
+            pending.fullscreen = false;
+        }
+    }
+
+    fn maximize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_toplevel(&surface) {
+            self.workspaces.set_maximized(&window, true, self.config.gaps);
+        } else if let Some(pending) = self.pending_window(&surface) {
+            pending.maximized = true;
+        }
+    }
+
+    fn unmaximize_request(&mut self, surface: ToplevelSurface) {
+        if let Some(window) = self.window_for_toplevel(&surface) {
+            self.workspaces.set_maximized(&window, false, self.config.gaps);
+        } else if let Some(pending) = self.pending_window(&surface) {
+            pending.maximized = false;
+        }
+    }
+
+    fn move_request(&mut self, surface: ToplevelSurface, seat: WlSeat, serial: Serial) {
+        let seat = Seat::from_resource(&seat).unwrap();
+        let Some(start_data) = check_grab(&seat, surface.wl_surface(), serial) else {
+            return;
+        };
+        let Some(window) = self.window_for_toplevel(&surface) else {
+            return;
+        };
+
+        let pointer = seat.get_pointer().unwrap();
+        if let Some(grab) = MoveSurfaceGrab::start(start_data, window, self) {
+            pointer.set_grab(self, grab, serial, Focus::Clear);
+        }
+    }
+
+    fn resize_request(
+        &mut self,
+        surface: ToplevelSurface,
+        seat: WlSeat,
+        serial: Serial,
+        edges: ResizeEdge,
+    ) {
+        let seat = Seat::from_resource(&seat).unwrap();
+        let Some(start_data) = check_grab(&seat, surface.wl_surface(), serial) else {
+            return;
+        };
+        let Some(window) = self.window_for_toplevel(&surface) else {
+            return;
+        };
+
+        let pointer = seat.get_pointer().unwrap();
+        let floating = self
+            .workspaces
+            .workspace_from_window(&window)
+            .map_or(false, |workspace| workspace.is_floating(&window));
+        if floating {
+            if let Some(grab) = ResizeSurfaceGrab::start(start_data, window, Some(edges), self) {
+                pointer.set_grab(self, grab, serial, Focus::Clear);
+            }
+        } else if let Some(grab) = ResizeTileGrab::start(start_data, window, self) {
+            pointer.set_grab(self, grab, serial, Focus::Clear);
+        }
+    }
+}
+
+impl<BackendData: Backend> MagmaState<BackendData> {
+    /// Floats or tiles a toplevel on its initial commit, the only time this is decided,
+    /// and applies the states it requested before.
+    pub fn map_toplevel(&mut self, pending: PendingWindow) {
+        let PendingWindow {
+            window,
+            fullscreen,
+            maximized,
+        } = pending;
+        let gaps = self.config.gaps;
+        let workspace = self.workspaces.current_mut();
+        match should_float(&window) {
+            Some(size) => workspace.map_floating(window.clone(), size),
+            None => layout_window(workspace, window.clone(), WindowLayoutEvent::Added, gaps),
+        }
+        if maximized {
+            self.workspaces.set_maximized(&window, true, gaps);
+        }
+        if fullscreen {
+            self.workspaces.set_fullscreen(&window, true, gaps);
+        }
+        self.set_input_focus(FocusTarget::Window(window));
+        self.ipc_manager.update_occupied_workspaces(&mut self.workspaces);
+    }
+
+    fn pending_window(&mut self, surface: &ToplevelSurface) -> Option<&mut PendingWindow> {
+        self.pending_windows
+            .iter_mut()
+            .find(|p| p.window.toplevel() == surface)
+    }
+
+    fn window_for_toplevel(&self, surface: &ToplevelSurface) -> Option<Window> {
+        self.workspaces
+            .all_windows()
+            .find(|w| w.toplevel() == surface)
+            .map(|w| w.clone())
+    }
+}
+
+/// Returns the data of the implicit grab `serial` refers to, if it was started
+/// by a button press on `surface`'s client.
+fn check_grab<BackendData: Backend>(
+    seat: &Seat<MagmaState<BackendData>>,
+    surface: &WlSurface,
+    serial: Serial,
+) -> Option<PointerGrabStartData<MagmaState<BackendData>>> {
+    let pointer = seat.get_pointer()?;
+    if !pointer.has_grab(serial) {
+        return None;
+    }
+    let start_data = pointer.grab_start_data()?;
+    let (focus, _) = start_data.focus.as_ref()?;
+    if !focus.same_client_as(&surface.id()) {
+        return None;
+    }
+    Some(start_data)
 }
 
 delegate_xdg_shell!(@<BackendData: Backend + 'static> MagmaState<BackendData>);
 
+/// Whether a toplevel should float instead of being tiled, which is the case for
+/// dialogs with a parent and windows with a fixed size.
+///
+/// Returns the size to float at, empty if the client may choose.
+pub fn should_float(window: &Window) -> Option<Size<i32, Logical>> {
+    let toplevel = window.toplevel();
+    if toplevel.parent().is_some() {
+        return Some(Size::from((0, 0)));
+    }
+    let (min_size, max_size) = with_states(toplevel.wl_surface(), |states| {
+        let state = states.cached_state.current::<SurfaceCachedState>();
+        (state.min_size, state.max_size)
+    });
+    (min_size.w > 0 && min_size.h > 0 && min_size == max_size).then_some(min_size)
+}
+
 /// Should be called on `WlSurface::commit`
 pub fn handle_commit(workspaces: &Workspaces, surface: &WlSurface, popup_manager: &PopupManager) -> Option<()> {
     if let Some(window) = workspaces
@@ -116,7 +290,7 @@ pub fn handle_commit(workspaces: &Workspaces, surface: &WlSurface, popup_manager
         }
     };
 
-    if let Some(output) = workspaces.current().outputs().find(|o| {
+    if let Some(output) = workspaces.outputs().find(|o| {
         let map = layer_map_for_output(o);
         map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL)
             .is_some()
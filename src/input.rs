@@ -1,50 +1,96 @@
+use std::time::Duration;
+
 use smithay::{
     backend::input::{
-        self, AbsolutePositionEvent, Axis, AxisSource, Event, InputBackend, InputEvent, KeyState,
-        KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
+        self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
+        GestureBeginEvent, GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _,
+        InputBackend, InputEvent, KeyState, ProximityState, TabletToolButtonEvent, TabletToolEvent,
+        TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TouchEvent, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
     },
     input::{
-        keyboard::{FilterResult, xkb},
-        pointer::{AxisFrame, ButtonEvent, MotionEvent, RelativeMotionEvent},
+        keyboard::{FilterResult, Keysym, KeysymHandle, ModifiersState, xkb},
+        pointer::{
+            AxisFrame, ButtonEvent, Focus, GestureHoldBeginEvent, GestureHoldEndEvent,
+            GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+            GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent,
+            GrabStartData as PointerGrabStartData, MotionEvent, RelativeMotionEvent,
+        },
+    },
+    output::Output,
+    reexports::{
+        calloop::timer::{TimeoutAction, Timer},
+        wayland_server::protocol::wl_surface::WlSurface,
+    },
+    utils::{Logical, Point, Size, SERIAL_COUNTER},
+    wayland::{
+        seat::WaylandFocus,
+        tablet_manager::{TabletDescriptor, TabletSeatTrait},
     },
-    utils::{Logical, Point, SERIAL_COUNTER},
 };
 
-use crate::{state::{Backend, MagmaState}, utils::focus::FocusTarget, backends::udev::UdevData, config::Action};
+use crate::{state::{Backend, MagmaState}, utils::{focus::FocusTarget, grabs::{MoveSurfaceGrab, ResizeSurfaceGrab, ResizeTileGrab}, swipe::WorkspaceSwipe, workspaces::output_geometry}, backends::udev::UdevData, config::{Action, Direction, GesturePattern, KeyPattern, MouseButton}};
+
+/// Distance of continuous scrolling that triggers a scroll binding once.
+const SCROLL_STEP: f64 = 15.0;
+/// Distance a swipe has to move to count as one in a direction.
+const GESTURE_THRESHOLD: f64 = 30.0;
+/// Distance the fingers have to move the workspaces to switch to the next one.
+const SWIPE_COMMIT_DISTANCE: f64 = 150.0;
+
+/// A touchpad swipe bound in the config, kept from clients.
+#[derive(Debug, Clone, Copy)]
+pub struct SwipeGesture {
+    pub fingers: u32,
+    /// Distance the fingers moved since the swipe began.
+    pub delta: Point<f64, Logical>,
+}
+
+/// The direction a swipe moved in the most, if it moved far enough.
+fn swipe_direction(delta: Point<f64, Logical>) -> Option<Direction> {
+    if delta.x.abs().max(delta.y.abs()) < GESTURE_THRESHOLD {
+        None
+    } else if delta.x.abs() > delta.y.abs() {
+        Some(if delta.x < 0.0 { Direction::Left } else { Direction::Right })
+    } else {
+        Some(if delta.y < 0.0 { Direction::Up } else { Direction::Down })
+    }
+}
+
+fn is_modifier(keysym: u32) -> bool {
+    (xkb::KEY_Shift_L..=xkb::KEY_Hyper_R).contains(&keysym) || keysym == xkb::KEY_ISO_Level3_Shift
+}
+
+/// Evdev code of the left mouse button.
+pub const BTN_LEFT: u32 = 0x110;
+/// Evdev code of the right mouse button.
+pub const BTN_RIGHT: u32 = 0x111;
+/// Evdev code of the middle mouse button.
+pub const BTN_MIDDLE: u32 = 0x112;
+/// Evdev code of the back side button.
+pub const BTN_SIDE: u32 = 0x113;
+/// Evdev code of the forward side button.
+pub const BTN_EXTRA: u32 = 0x114;
+
+fn mouse_button(code: u32) -> Option<MouseButton> {
+    match code {
+        BTN_LEFT => Some(MouseButton::Button1),
+        BTN_MIDDLE => Some(MouseButton::Button2),
+        BTN_RIGHT => Some(MouseButton::Button3),
+        BTN_SIDE => Some(MouseButton::Button8),
+        BTN_EXTRA => Some(MouseButton::Button9),
+        _ => None,
+    }
+}
+
 impl MagmaState<UdevData> {
     pub fn process_input_event_udev<I: InputBackend>(&mut self, event: InputEvent<I>) -> Option<i32> {
         match event {
             InputEvent::Keyboard { event, .. } => {
-                let serial = SERIAL_COUNTER.next_serial();
-                let time = Event::time_msec(&event);
-
-                if let Some(action) = self.seat.get_keyboard().unwrap().input(
-                    self,
-                    event.key_code(),
-                    event.state(),
-                    serial,
-                    time,
-                    |data, modifiers, handle| {
-                        for (binding, action) in data.config.keybindings.iter() {
-                            if event.state() == KeyState::Pressed
-                                && binding.modifiers == *modifiers
-                                && handle.raw_syms().contains(&binding.key)
-                            {
-                                return FilterResult::Intercept(action.clone());
-                            } else if (xkb::KEY_XF86Switch_VT_1..=xkb::KEY_XF86Switch_VT_12).contains(&handle.modified_sym()) {
-                                // VTSwitch
-                                let vt = (&handle.modified_sym() - xkb::KEY_XF86Switch_VT_1 + 1) as i32;
-                                return FilterResult::Intercept(Action::VTSwitch(vt));
-                            }
-                        }
-                        FilterResult::Forward
-                    },
-                ) {
-                    match action {
-                        Action::VTSwitch(vt) => return Some(vt),
-                        _ => self.handle_action(action),
-                    }
-                };
+                match self.keyboard_action::<I>(event) {
+                    Some(Action::VTSwitch(vt)) => return Some(vt),
+                    Some(action) => self.handle_action(action),
+                    None => {}
+                }
                 None
             }
             event => {self.process_input_event(event); None}
@@ -57,38 +103,37 @@ impl<BackendData: Backend> MagmaState<BackendData> {
     pub fn process_input_event<I: InputBackend>(&mut self, event: InputEvent<I>) {
         match event {
             InputEvent::Keyboard { event, .. } => {
-                let serial = SERIAL_COUNTER.next_serial();
-                let time = Event::time_msec(&event);
-
-                if let Some(action) = self.seat.get_keyboard().unwrap().input(
-                    self,
-                    event.key_code(),
-                    event.state(),
-                    serial,
-                    time,
-                    |data, modifiers, handle| {
-                        for (binding, action) in data.config.keybindings.iter() {
-                            if event.state() == KeyState::Pressed
-                                && binding.modifiers == *modifiers
-                                && handle.raw_syms().contains(&binding.key)
-                            {
-                                return FilterResult::Intercept(action.clone());
-                            }
-                        }
-                        FilterResult::Forward
-                    },
-                ) {
+                if let Some(action) = self.keyboard_action::<I>(event) {
                     self.handle_action(action);
-                };
+                }
+            }
+            InputEvent::DeviceAdded { device } => {
+                if device.has_capability(DeviceCapability::Keyboard) {
+                    self.set_active_keyboard(&device.name());
+                }
+                if device.has_capability(DeviceCapability::TabletTool) {
+                    self.seat
+                        .tablet_seat()
+                        .add_tablet::<Self>(&self.dh, &TabletDescriptor::from(&device));
+                }
+            }
+            InputEvent::DeviceRemoved { device } => {
+                if device.has_capability(DeviceCapability::TabletTool) {
+                    let tablet_seat = self.seat.tablet_seat();
+                    tablet_seat.remove_tablet(&TabletDescriptor::from(&device));
+                    if tablet_seat.count_tablets() == 0 {
+                        tablet_seat.clear_tools();
+                    }
+                }
             }
             InputEvent::PointerMotion { event } => {
                 let serial = SERIAL_COUNTER.next_serial();
                 let delta = (event.delta_x(), event.delta_y()).into();
-                self.pointer_location += delta;
 
                 // clamp to screen limits
                 // this event is never generated by winit
-                self.pointer_location = self.clamp_coords(self.pointer_location);
+                self.pointer_location = self.clamp_coords(self.pointer_location + delta);
+                self.focus_output_under_pointer();
 
                 let under = self.surface_under();
 
@@ -117,9 +162,13 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                 }
             }
             InputEvent::PointerMotionAbsolute { event, .. } => {
-                let output = self.workspaces.current().outputs().next().unwrap().clone();
+                let output = self
+                    .workspaces
+                    .current_output()
+                    .or_else(|| self.workspaces.outputs().next())
+                    .unwrap();
 
-                let output_geo = self.workspaces.current().output_geometry(&output).unwrap();
+                let output_geo = output_geometry(output).unwrap();
 
                 let pos = event.position_transformed(output_geo.size) + output_geo.loc.to_f64();
 
@@ -128,6 +177,7 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                 let pointer = self.seat.get_pointer().unwrap();
 
                 self.pointer_location = self.clamp_coords(pos);
+                self.focus_output_under_pointer();
 
                 let under = self.surface_under();
 
@@ -137,7 +187,7 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                     self,
                     under,
                     &MotionEvent {
-                        location: pos,
+                        location: self.pointer_location,
                         serial,
                         time: event.time_msec(),
                     },
@@ -154,6 +204,49 @@ impl<BackendData: Backend> MagmaState<BackendData> {
 
                 self.set_input_focus_auto();
 
+                if button_state == ButtonState::Pressed {
+                    self.last_pressed_key = None;
+                    let modifiers = self.seat.get_keyboard().unwrap().modifier_state();
+                    if let Some(action) =
+                        mouse_button(button).and_then(|button| self.mousebinding(&modifiers, button))
+                    {
+                        self.suppressed_buttons.push(button);
+                        self.handle_action(action);
+                        return;
+                    }
+                } else if let Some(index) = self.suppressed_buttons.iter().position(|b| *b == button) {
+                    self.suppressed_buttons.remove(index);
+                    return;
+                }
+
+                if button_state == ButtonState::Pressed
+                    && !pointer.is_grabbed()
+                    && self.seat.get_keyboard().unwrap().modifier_state().logo
+                {
+                    if let Some((window, _)) = self.window_under() {
+                        let start_data = PointerGrabStartData {
+                            focus: None,
+                            button,
+                            location: self.pointer_location,
+                        };
+                        if button == BTN_LEFT {
+                            if let Some(grab) = MoveSurfaceGrab::start(start_data, window, self) {
+                                pointer.set_grab(self, grab, serial, Focus::Clear);
+                            }
+                        } else if button == BTN_RIGHT {
+                            if self.workspaces.current().is_floating(&window) {
+                                if let Some(grab) =
+                                    ResizeSurfaceGrab::start(start_data, window, None, self)
+                                {
+                                    pointer.set_grab(self, grab, serial, Focus::Clear);
+                                }
+                            } else if let Some(grab) = ResizeTileGrab::start(start_data, window, self) {
+                                pointer.set_grab(self, grab, serial, Focus::Clear);
+                            }
+                        }
+                    }
+                }
+
                 pointer.button(
                     self,
                     &ButtonEvent {
@@ -164,6 +257,65 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                     },
                 );
             }
+            InputEvent::GestureSwipeBegin { event } => self.on_gesture_swipe_begin::<I>(event),
+            InputEvent::GestureSwipeUpdate { event } => self.on_gesture_swipe_update::<I>(event),
+            InputEvent::GestureSwipeEnd { event } => self.on_gesture_swipe_end::<I>(event),
+            InputEvent::GesturePinchBegin { event } => {
+                let pointer = self.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_begin(
+                    self,
+                    &GesturePinchBeginEvent {
+                        serial: SERIAL_COUNTER.next_serial(),
+                        time: event.time_msec(),
+                        fingers: event.fingers(),
+                    },
+                );
+            }
+            InputEvent::GesturePinchUpdate { event } => {
+                let pointer = self.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_update(
+                    self,
+                    &GesturePinchUpdateEvent {
+                        time: event.time_msec(),
+                        delta: event.delta(),
+                        scale: event.scale(),
+                        rotation: event.rotation(),
+                    },
+                );
+            }
+            InputEvent::GesturePinchEnd { event } => {
+                let pointer = self.seat.get_pointer().unwrap();
+                pointer.gesture_pinch_end(
+                    self,
+                    &GesturePinchEndEvent {
+                        serial: SERIAL_COUNTER.next_serial(),
+                        time: event.time_msec(),
+                        cancelled: event.cancelled(),
+                    },
+                );
+            }
+            InputEvent::GestureHoldBegin { event } => {
+                let pointer = self.seat.get_pointer().unwrap();
+                pointer.gesture_hold_begin(
+                    self,
+                    &GestureHoldBeginEvent {
+                        serial: SERIAL_COUNTER.next_serial(),
+                        time: event.time_msec(),
+                        fingers: event.fingers(),
+                    },
+                );
+            }
+            InputEvent::GestureHoldEnd { event } => {
+                let pointer = self.seat.get_pointer().unwrap();
+                pointer.gesture_hold_end(
+                    self,
+                    &GestureHoldEndEvent {
+                        serial: SERIAL_COUNTER.next_serial(),
+                        time: event.time_msec(),
+                        cancelled: event.cancelled(),
+                    },
+                );
+            }
             InputEvent::PointerAxis { event, .. } => {
                 let horizontal_amount =
                     event.amount(input::Axis::Horizontal).unwrap_or_else(|| {
@@ -178,43 +330,678 @@ impl<BackendData: Backend> MagmaState<BackendData> {
                 let horizontal_amount_discrete = event.amount_discrete(input::Axis::Horizontal);
                 let vertical_amount_discrete = event.amount_discrete(input::Axis::Vertical);
 
+                let horizontal_bound =
+                    self.scroll_binding(Axis::Horizontal, horizontal_amount, horizontal_amount_discrete);
+                let vertical_bound =
+                    self.scroll_binding(Axis::Vertical, vertical_amount, vertical_amount_discrete);
+                if (horizontal_bound || vertical_bound)
+                    && (horizontal_bound || horizontal_amount == 0.0)
+                    && (vertical_bound || vertical_amount == 0.0)
+                {
+                    return;
+                }
+
                 {
                     let mut frame = AxisFrame::new(event.time_msec()).source(event.source());
-                    if horizontal_amount != 0.0 {
+                    if !horizontal_bound && horizontal_amount != 0.0 {
                         frame = frame.value(Axis::Horizontal, horizontal_amount);
                         if let Some(discrete) = horizontal_amount_discrete {
                             frame = frame.discrete(Axis::Horizontal, discrete as i32);
                         }
-                    } else if event.source() == AxisSource::Finger {
+                    } else if horizontal_amount == 0.0 && event.source() == AxisSource::Finger {
                         frame = frame.stop(Axis::Horizontal);
                     }
-                    if vertical_amount != 0.0 {
+                    if !vertical_bound && vertical_amount != 0.0 {
                         frame = frame.value(Axis::Vertical, vertical_amount);
                         if let Some(discrete) = vertical_amount_discrete {
                             frame = frame.discrete(Axis::Vertical, discrete as i32);
                         }
-                    } else if event.source() == AxisSource::Finger {
+                    } else if vertical_amount == 0.0 && event.source() == AxisSource::Finger {
                         frame = frame.stop(Axis::Vertical);
                     }
                     self.seat.get_pointer().unwrap().axis(self, frame);
                 }
             }
+            InputEvent::TouchDown { event } => self.on_touch_down::<I>(event),
+            InputEvent::TouchMotion { event } => self.on_touch_motion::<I>(event),
+            InputEvent::TouchUp { event } => {
+                if let Some(touch) = self.seat.get_touch() {
+                    let serial = SERIAL_COUNTER.next_serial();
+                    touch.up(serial, event.time_msec(), event.slot());
+                }
+            }
+            InputEvent::TouchCancel { .. } => {
+                if let Some(touch) = self.seat.get_touch() {
+                    touch.cancel();
+                }
+            }
+            InputEvent::TouchFrame { .. } => {
+                if let Some(touch) = self.seat.get_touch() {
+                    touch.frame();
+                }
+            }
+            InputEvent::TabletToolProximity { event } => self.on_tablet_tool_proximity::<I>(event),
+            InputEvent::TabletToolAxis { event } => self.on_tablet_tool_axis::<I>(event),
+            InputEvent::TabletToolTip { event } => {
+                if let Some(tool) = self.seat.tablet_seat().get_tool(&event.tool()) {
+                    match event.tip_state() {
+                        TabletToolTipState::Down => {
+                            tool.tip_down(SERIAL_COUNTER.next_serial(), event.time_msec());
+                            self.set_input_focus_auto();
+                        }
+                        TabletToolTipState::Up => tool.tip_up(event.time_msec()),
+                    }
+                }
+            }
+            InputEvent::TabletToolButton { event } => {
+                if let Some(tool) = self.seat.tablet_seat().get_tool(&event.tool()) {
+                    tool.button(
+                        event.button(),
+                        event.button_state(),
+                        SERIAL_COUNTER.next_serial(),
+                        event.time_msec(),
+                    );
+                }
+            }
             _ => {}
         }
     }
 
+    /// Keeps swipes with as many fingers as a bound gesture from clients.
+    fn on_gesture_swipe_begin<I: InputBackend>(&mut self, event: I::GestureSwipeBeginEvent) {
+        let fingers = event.fingers();
+        if self.config.gestures.keys().any(|pattern| pattern.fingers == fingers) {
+            self.swipe_gesture = Some(SwipeGesture {
+                fingers,
+                delta: Point::default(),
+            });
+            return;
+        }
+        let pointer = self.seat.get_pointer().unwrap();
+        pointer.gesture_swipe_begin(
+            self,
+            &GestureSwipeBeginEvent {
+                serial: SERIAL_COUNTER.next_serial(),
+                time: event.time_msec(),
+                fingers,
+            },
+        );
+    }
+
+    fn on_gesture_swipe_update<I: InputBackend>(&mut self, event: I::GestureSwipeUpdateEvent) {
+        let Some(gesture) = self.swipe_gesture.as_mut() else {
+            let pointer = self.seat.get_pointer().unwrap();
+            pointer.gesture_swipe_update(
+                self,
+                &GestureSwipeUpdateEvent {
+                    time: event.time_msec(),
+                    delta: event.delta(),
+                },
+            );
+            return;
+        };
+        gesture.delta += event.delta();
+
+        let (fingers, delta) = (gesture.fingers, gesture.delta);
+        let distance = if delta.x.abs() > delta.y.abs() { delta.x } else { 0.0 };
+        self.follow_workspace_swipe(fingers, distance);
+    }
+
+    /// Runs the action of a bound swipe, or lets the workspaces following it settle.
+    fn on_gesture_swipe_end<I: InputBackend>(&mut self, event: I::GestureSwipeEndEvent) {
+        let Some(gesture) = self.swipe_gesture.take() else {
+            let pointer = self.seat.get_pointer().unwrap();
+            pointer.gesture_swipe_end(
+                self,
+                &GestureSwipeEndEvent {
+                    serial: SERIAL_COUNTER.next_serial(),
+                    time: event.time_msec(),
+                    cancelled: event.cancelled(),
+                },
+            );
+            return;
+        };
+
+        let action = swipe_direction(gesture.delta)
+            .map(|direction| GesturePattern {
+                fingers: gesture.fingers,
+                direction,
+            })
+            .and_then(|pattern| self.config.gestures.get(&pattern))
+            .cloned();
+        match action {
+            Some(Action::NextWorkspace | Action::PrevWorkspace) => {
+                self.finish_workspace_swipe(event.cancelled());
+            }
+            Some(action) if !event.cancelled() => {
+                self.finish_workspace_swipe(true);
+                self.handle_action(action);
+            }
+            _ => self.finish_workspace_swipe(true),
+        }
+    }
+
+    /// Moves the workspaces of the focused output `distance` to the right along
+    /// with a swipe, if the swipe in that direction switches workspaces.
+    fn follow_workspace_swipe(&mut self, fingers: u32, distance: f64) {
+        let direction = if distance < 0.0 { Direction::Left } else { Direction::Right };
+        let forward = match self.config.gestures.get(&GesturePattern { fingers, direction }) {
+            Some(Action::NextWorkspace) => Some(true),
+            Some(Action::PrevWorkspace) => Some(false),
+            _ => None,
+        };
+        let following = self
+            .workspaces
+            .swipe
+            .as_ref()
+            .map_or(false, WorkspaceSwipe::is_following);
+        if forward.is_none() && !following {
+            return;
+        }
+        let Some(output) = self.workspaces.current_output().cloned() else {
+            return;
+        };
+
+        let other = forward.and_then(|forward| self.workspaces.neighbour(&output, forward));
+        if let Some(id) = other {
+            self.workspaces.arrange_on(id, &output, self.config.gaps);
+        }
+        let swipe = match &mut self.workspaces.swipe {
+            Some(swipe) if swipe.output == output => swipe,
+            swipe => swipe.insert(WorkspaceSwipe::new(output)),
+        };
+        swipe.update(other, distance);
+    }
+
+    /// Switches to the workspace revealed by the swipe if it moved far enough,
+    /// and lets the workspaces slide into place.
+    fn finish_workspace_swipe(&mut self, cancelled: bool) {
+        let Some(swipe) = self.workspaces.swipe.as_mut().filter(|swipe| swipe.is_following()) else {
+            return;
+        };
+        let distance = swipe.distance();
+        let Some(id) = swipe
+            .other
+            .filter(|_| !cancelled && distance.abs() >= SWIPE_COMMIT_DISTANCE)
+        else {
+            let other = swipe.other;
+            swipe.settle(other, distance);
+            return;
+        };
+
+        let output = swipe.output.clone();
+        let previous = self.workspaces.active_id_on(&output);
+        self.workspaces.activate(id, &mut self.ipc_manager, self.config.gaps);
+        self.refocus();
+
+        // the new workspace continues from where the fingers left it
+        let width = output_geometry(&output).map_or(0, |geometry| geometry.size.w) as f64;
+        let offset = if distance < 0.0 { distance + width } else { distance - width };
+        let mut swipe = WorkspaceSwipe::new(output);
+        swipe.settle(previous, offset);
+        self.workspaces.swipe = Some(swipe);
+    }
+
+    /// Where on the desktop an absolute position of `device` points, on the
+    /// output given by `map_to_output` in its input config, else the first one.
+    fn mapped_location<D: Device>(
+        &self,
+        device: &D,
+        kind: &str,
+        position: impl FnOnce(Size<i32, Logical>) -> Point<f64, Logical>,
+    ) -> Option<Point<f64, Logical>> {
+        let config = self.config.input_config(&device.name(), &[kind]);
+        let geometry = config
+            .map_to_output
+            .and_then(|name| self.workspaces.outputs().find(|output| output.name() == name))
+            .or_else(|| self.workspaces.outputs().next())
+            .and_then(output_geometry)?;
+        Some(position(geometry.size) + geometry.loc.to_f64())
+    }
+
+    /// Sends a new touch point to the surface under it, focusing its window.
+    fn on_touch_down<I: InputBackend>(&mut self, event: I::TouchDownEvent) {
+        let Some(touch) = self.seat.get_touch() else {
+            return;
+        };
+        let Some(location) = self.mapped_location(&event.device(), "touch", |size| {
+            event.position_transformed(size)
+        }) else {
+            return;
+        };
+        if let Some(output) = self.workspaces.output_under(location).cloned() {
+            self.workspaces.focus_output(&output, &mut self.ipc_manager);
+        }
+        let Some((target, surface_location)) = self.surface_at(location) else {
+            return;
+        };
+        self.set_input_focus(target.clone());
+        if let Some(surface) = target.wl_surface() {
+            let serial = SERIAL_COUNTER.next_serial();
+            touch.down(
+                serial,
+                event.time_msec(),
+                &surface,
+                surface_location,
+                event.slot(),
+                location,
+            );
+        }
+    }
+
+    fn on_touch_motion<I: InputBackend>(&mut self, event: I::TouchMotionEvent) {
+        let Some(touch) = self.seat.get_touch() else {
+            return;
+        };
+        if let Some(location) = self.mapped_location(&event.device(), "touch", |size| {
+            event.position_transformed(size)
+        }) {
+            touch.motion(event.time_msec(), event.slot(), location);
+        }
+    }
+
+    /// Moves the pointer along with a tablet tool, so the surface under the
+    /// tool is known and the cursor follows it.
+    fn move_pointer_with_tool(
+        &mut self,
+        location: Point<f64, Logical>,
+        time: u32,
+    ) -> Option<(WlSurface, Point<i32, Logical>)> {
+        self.pointer_location = self.clamp_coords(location);
+        self.focus_output_under_pointer();
+
+        let under = self.surface_under();
+        let pointer = self.seat.get_pointer().unwrap();
+        pointer.motion(
+            self,
+            under.clone(),
+            &MotionEvent {
+                location: self.pointer_location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time,
+            },
+        );
+        under.and_then(|(target, location)| target.wl_surface().map(|surface| (surface, location)))
+    }
+
+    fn on_tablet_tool_proximity<I: InputBackend>(&mut self, event: I::TabletToolProximityEvent) {
+        let device = event.device();
+        let Some(location) = self.mapped_location(&device, "tablet", |size| {
+            event.position_transformed(size)
+        }) else {
+            return;
+        };
+        let tablet_seat = self.seat.tablet_seat();
+        tablet_seat.add_tool::<Self>(&self.dh, &event.tool());
+
+        let under = self.move_pointer_with_tool(location, event.time_msec());
+        let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&device));
+        let tool = tablet_seat.get_tool(&event.tool());
+        if let (Some(tablet), Some(tool)) = (tablet, tool) {
+            match (event.state(), under) {
+                (ProximityState::In, Some(under)) => tool.proximity_in(
+                    self.pointer_location,
+                    under,
+                    &tablet,
+                    SERIAL_COUNTER.next_serial(),
+                    event.time_msec(),
+                ),
+                (ProximityState::In, None) => {}
+                (ProximityState::Out, _) => tool.proximity_out(event.time_msec()),
+            }
+        }
+    }
+
+    /// Moves a tablet tool, passing pressure, tilt and the other axes that
+    /// changed on to the client.
+    fn on_tablet_tool_axis<I: InputBackend>(&mut self, event: I::TabletToolAxisEvent) {
+        let device = event.device();
+        let Some(location) = self.mapped_location(&device, "tablet", |size| {
+            event.position_transformed(size)
+        }) else {
+            return;
+        };
+        let under = self.move_pointer_with_tool(location, event.time_msec());
+
+        let tablet_seat = self.seat.tablet_seat();
+        let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&device));
+        let tool = tablet_seat.get_tool(&event.tool());
+        let (Some(tablet), Some(tool)) = (tablet, tool) else {
+            return;
+        };
+        if event.pressure_has_changed() {
+            tool.pressure(event.pressure());
+        }
+        if event.distance_has_changed() {
+            tool.distance(event.distance());
+        }
+        if event.tilt_has_changed() {
+            tool.tilt(event.tilt());
+        }
+        if event.slider_has_changed() {
+            tool.slider_position(event.slider_position());
+        }
+        if event.rotation_has_changed() {
+            tool.rotation(event.rotation());
+        }
+        if event.wheel_has_changed() {
+            tool.wheel(event.wheel_delta(), event.wheel_delta_discrete());
+        }
+        tool.motion(
+            self.pointer_location,
+            under,
+            &tablet,
+            SERIAL_COUNTER.next_serial(),
+            event.time_msec(),
+        );
+    }
+
+    /// Passes a key event to the keyboard, returning the action of the
+    /// keybinding it triggered instead of forwarding it to the focused client.
+    ///
+    /// Keys that start or continue a key sequence are held back from the client
+    /// as well, together with the releases of all intercepted keys.
+    fn keyboard_action<I: InputBackend>(&mut self, event: I::KeyboardKeyEvent) -> Option<Action> {
+        let serial = SERIAL_COUNTER.next_serial();
+        let time = Event::time_msec(&event);
+        let key_state = event.state();
+        let key_code = event.key_code();
+
+        let device = Event::device(&event).name();
+        if self.active_keyboard.as_deref() != Some(device.as_str()) {
+            self.set_active_keyboard(&device);
+        }
+
+        if key_state == KeyState::Pressed
+            || self.key_repeat.as_ref().map_or(false, |(key, _)| *key == key_code)
+        {
+            self.stop_key_repeat();
+        }
+
+        let mut released = None;
+        let action = self
+            .seat
+            .get_keyboard()
+            .unwrap()
+            .input(self, key_code, key_state, serial, time, |data, modifiers, handle| {
+                if key_state != KeyState::Pressed {
+                    if data.last_pressed_key.take() == Some(key_code) {
+                        let syms = data.binding_syms(&handle);
+                        released = data
+                            .config
+                            .keybindings(data.mode.as_deref())
+                            .iter()
+                            .find(|(binding, _)| {
+                                binding.release && binding.matches(modifiers, handle.raw_code(), &syms)
+                            })
+                            .map(|(_, action)| action.clone());
+                    }
+                    return match data.suppressed_keys.iter().position(|key| *key == key_code) {
+                        Some(index) => {
+                            data.suppressed_keys.remove(index);
+                            FilterResult::Intercept(None)
+                        }
+                        None => FilterResult::Forward,
+                    };
+                }
+
+                data.last_pressed_key = Some(key_code);
+                let result = data.match_keybinding(modifiers, &handle, key_code);
+                if let FilterResult::Intercept(_) = result {
+                    data.suppressed_keys.push(key_code);
+                }
+                result
+            })
+            .flatten();
+        action.or(released)
+    }
+
+    fn match_keybinding(
+        &mut self,
+        modifiers: &ModifiersState,
+        handle: &KeysymHandle<'_>,
+        key_code: u32,
+    ) -> FilterResult<Option<Action>> {
+        if (xkb::KEY_XF86Switch_VT_1..=xkb::KEY_XF86Switch_VT_12).contains(&handle.modified_sym()) {
+            // VTSwitch
+            let vt = (handle.modified_sym() - xkb::KEY_XF86Switch_VT_1 + 1) as i32;
+            return FilterResult::Intercept(Some(Action::VTSwitch(vt)));
+        }
+
+        let code = handle.raw_code();
+        let syms = self.binding_syms(handle);
+
+        // continue or complete a started sequence, or start a new one
+        let depth = self.pending_keys.len();
+        let mut next = None;
+        for (sequence, action) in self.config.sequences(self.mode.as_deref()) {
+            let patterns = &sequence.0;
+            if patterns.len() <= depth
+                || patterns[..depth] != self.pending_keys[..]
+                || !patterns[depth].matches(modifiers, code, &syms)
+            {
+                continue;
+            }
+            if patterns.len() == depth + 1 {
+                let action = action.clone();
+                self.cancel_key_sequence();
+                return FilterResult::Intercept(Some(action));
+            }
+            next = Some(patterns[depth].clone());
+        }
+        if let Some(pattern) = next {
+            self.continue_key_sequence(pattern);
+            return FilterResult::Intercept(None);
+        }
+        if depth > 0 {
+            // modifiers may be pressed for the next pattern, anything else
+            // ends the sequence without reaching the client
+            if is_modifier(handle.modified_sym()) {
+                return FilterResult::Forward;
+            }
+            self.cancel_key_sequence();
+            return FilterResult::Intercept(None);
+        }
+
+        let keybindings = self.config.keybindings(self.mode.as_deref());
+        if let Some((binding, action)) = keybindings
+            .iter()
+            .find(|(binding, _)| !binding.release && binding.matches(modifiers, code, &syms))
+        {
+            let action = action.clone();
+            if binding.repeat {
+                self.start_key_repeat(key_code, action.clone());
+            }
+            return FilterResult::Intercept(Some(action));
+        }
+        // keys bound on release don't reach the client either, unless they are
+        // modifiers that are needed for other bindings
+        if !is_modifier(handle.modified_sym())
+            && keybindings
+                .keys()
+                .any(|binding| binding.release && binding.key.matches(code, &syms))
+        {
+            return FilterResult::Intercept(None);
+        }
+        FilterResult::Forward
+    }
+
+    /// Gives the seat's keyboard the repeat settings of the keyboard `name`,
+    /// which clients use until keys of another keyboard are pressed.
+    pub fn set_active_keyboard(&mut self, name: &str) {
+        let repeat_info = self.config.input_config(name, &["keyboard"]).repeat_info();
+        self.active_keyboard = Some(name.to_string());
+        if repeat_info != self.key_repeat_info {
+            self.key_repeat_info = repeat_info;
+            let (delay, rate) = repeat_info;
+            self.seat.get_keyboard().unwrap().change_repeat_info(rate, delay);
+        }
+    }
+
+    /// Keysyms of the key in `handle` to match bindings against, taken from the
+    /// first layout with `bind_base_layout` or else from the active one.
+    fn binding_syms(&self, handle: &KeysymHandle<'_>) -> Vec<Keysym> {
+        match &self.base_keymap {
+            Some(keymap) => keymap.key_get_syms_by_level(handle.raw_code(), 0, 0).to_vec(),
+            None => handle.raw_syms().to_vec(),
+        }
+    }
+
+    /// Runs `action` again at the repeat rate until `key_code` is released.
+    fn start_key_repeat(&mut self, key_code: u32, action: Action) {
+        self.stop_key_repeat();
+        let (delay, rate) = self.key_repeat_info;
+        if rate <= 0 {
+            return;
+        }
+        let timer = Timer::from_duration(Duration::from_millis(delay as u64));
+        let token = self
+            .loop_handle
+            .insert_source(timer, move |_, _, data| {
+                data.state.handle_action(action.clone());
+                TimeoutAction::ToDuration(Duration::from_millis(1000 / rate as u64))
+            })
+            .ok();
+        self.key_repeat = token.map(|token| (key_code, token));
+    }
+
+    fn stop_key_repeat(&mut self) {
+        if let Some((_, token)) = self.key_repeat.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+
+    /// The action bound to `button` with `modifiers` in the active mode.
+    fn mousebinding(&self, modifiers: &ModifiersState, button: MouseButton) -> Option<Action> {
+        self.config
+            .mousebindings(self.mode.as_deref())
+            .iter()
+            .find(|(pattern, _)| pattern.button == button && pattern.modifiers == *modifiers)
+            .map(|(_, action)| action.clone())
+    }
+
+    /// Runs the binding of scrolling `amount` along `axis`, if any, returning
+    /// whether the scroll was bound and must not reach the client.
+    fn scroll_binding(&mut self, axis: Axis, amount: f64, discrete: Option<f64>) -> bool {
+        if amount == 0.0 {
+            return false;
+        }
+        let button = match (axis, amount < 0.0) {
+            (Axis::Vertical, true) => MouseButton::ScrollUp,
+            (Axis::Vertical, false) => MouseButton::ScrollDown,
+            (Axis::Horizontal, true) => MouseButton::ScrollLeft,
+            (Axis::Horizontal, false) => MouseButton::ScrollRight,
+        };
+        let modifiers = self.seat.get_keyboard().unwrap().modifier_state();
+        let Some(action) = self.mousebinding(&modifiers, button) else {
+            return false;
+        };
+        self.last_pressed_key = None;
+
+        // wheels trigger once per detent, continuous scrolling every SCROLL_STEP
+        let remainder = match axis {
+            Axis::Horizontal => &mut self.scroll_remainder.0,
+            Axis::Vertical => &mut self.scroll_remainder.1,
+        };
+        let steps = match discrete {
+            Some(discrete) => discrete.abs().round().max(1.0) as u32,
+            None => {
+                if *remainder * amount < 0.0 {
+                    *remainder = 0.0;
+                }
+                *remainder += amount;
+                let steps = (remainder.abs() / SCROLL_STEP) as u32;
+                *remainder -= steps as f64 * SCROLL_STEP * amount.signum();
+                steps
+            }
+        };
+        for _ in 0..steps {
+            self.handle_action(action.clone());
+        }
+        true
+    }
+
+    /// Adds `pattern` to the pending key sequence and restarts its timeout.
+    fn continue_key_sequence(&mut self, pattern: KeyPattern) {
+        self.pending_keys.push(pattern);
+        if let Some(token) = self.sequence_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        let timer = Timer::from_duration(Duration::from_millis(self.config.sequence_timeout));
+        self.sequence_timer = self
+            .loop_handle
+            .insert_source(timer, |_, _, data| {
+                data.state.sequence_timer = None;
+                data.state.cancel_key_sequence();
+                TimeoutAction::Drop
+            })
+            .ok();
+        self.ipc_manager.update_pending_keys(&self.pending_keys);
+    }
+
+    /// Forgets the pending key sequence, if any.
+    pub fn cancel_key_sequence(&mut self) {
+        if let Some(token) = self.sequence_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        if !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            self.ipc_manager.update_pending_keys(&self.pending_keys);
+        }
+    }
+
+    /// Keeps the pointer inside the output layout, positions outside of every output
+    /// are clamped to the output the pointer is currently on.
     fn clamp_coords(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
-        if self.workspaces.current().outputs().next().is_none() {
+        if self.workspaces.output_under(pos).is_some() {
             return pos;
         }
+        let Some(geometry) = self
+            .workspaces
+            .output_under(self.pointer_location)
+            .or_else(|| self.workspaces.outputs().next())
+            .and_then(output_geometry)
+        else {
+            return pos;
+        };
 
         let (pos_x, pos_y) = pos.into();
-        let (max_x, max_y) = self.workspaces.current().output_geometry(self.workspaces.current().outputs().next().unwrap()).unwrap().size.into();
-        let clamped_x = pos_x.max(0.0).min(max_x as f64);
-        let clamped_y = pos_y.max(0.0).min(max_y as f64);
+        let clamped_x = pos_x
+            .max(geometry.loc.x as f64)
+            .min((geometry.loc.x + geometry.size.w) as f64);
+        let clamped_y = pos_y
+            .max(geometry.loc.y as f64)
+            .min((geometry.loc.y + geometry.size.h) as f64);
         (clamped_x, clamped_y).into()
     }
 
+    /// Moves the pointer to the center of `output`, so focus follows it there.
+    pub fn warp_pointer_to_output(&mut self, output: &Output) {
+        let Some(geometry) = output_geometry(output) else {
+            return;
+        };
+        self.pointer_location = (geometry.loc + geometry.size.to_point().downscale(2)).to_f64();
+
+        let under = self.surface_under();
+        let pointer = self.seat.get_pointer().unwrap();
+        pointer.motion(
+            self,
+            under,
+            &MotionEvent {
+                location: self.pointer_location,
+                serial: SERIAL_COUNTER.next_serial(),
+                time: self.start_time.elapsed().as_millis() as u32,
+            },
+        );
+    }
+
+    /// Gives focus to the workspace of the output the pointer moved onto.
+    fn focus_output_under_pointer(&mut self) {
+        if let Some(output) = self.workspaces.output_under(self.pointer_location).cloned() {
+            self.workspaces.focus_output(&output, &mut self.ipc_manager);
+        }
+    }
+
     pub fn set_input_focus(&mut self, target: FocusTarget){
             let keyboard = self.seat.get_keyboard().unwrap();
             let serial = SERIAL_COUNTER.next_serial();
@@ -227,5 +1014,28 @@ impl<BackendData: Backend> MagmaState<BackendData> {
             self.set_input_focus(d.0);
         }
     }
+
+    /// Restores keyboard focus on the current workspace.
+    ///
+    /// Prefers the workspace's focused window, then the window under the pointer,
+    /// then the first window of the workspace, clearing focus if there is none.
+    pub fn refocus(&mut self) {
+        let mut window = self.workspaces.current().focused().cloned();
+        if window.is_none() {
+            window = self.window_under().map(|(w, _)| w);
+        }
+        if window.is_none() {
+            window = self.workspaces.current().windows().next().map(|w| w.clone());
+        }
+
+        match window {
+            Some(window) => self.set_input_focus(FocusTarget::Window(window)),
+            None => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                let serial = SERIAL_COUNTER.next_serial();
+                keyboard.set_focus(self, None, serial);
+            }
+        }
+    }
 }
 
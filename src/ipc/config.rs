@@ -0,0 +1,45 @@
+use smithay::reexports::wayland_server::Dispatch;
+
+use super::{generated::config::Config, MagmaIpcManager, MagmaIpcHandler};
+
+impl<D> Dispatch<Config, (), D> for MagmaIpcManager
+where
+    D: Dispatch<Config, ()>,
+    D: MagmaIpcHandler,
+    D: 'static, {
+    fn request(
+        _state: &mut D,
+        _client: &smithay::reexports::wayland_server::Client,
+        _resource: &Config,
+        _request: <Config as smithay::reexports::wayland_server::Resource>::Request,
+        _data: &(),
+        _dhandle: &smithay::reexports::wayland_server::DisplayHandle,
+        _data_init: &mut smithay::reexports::wayland_server::DataInit<'_, D>,
+    ) {
+
+    }
+}
+
+impl MagmaIpcManager {
+    /// Tells subscribers the config was applied, except for the parts in `warnings`,
+    /// which are kept for clients subscribing later on.
+    pub fn config_reloaded(&mut self, warnings: Vec<String>) {
+        self.config_error = None;
+        for config_handle in self.config_handles.iter() {
+            config_handle.reloaded();
+            for warning in warnings.iter() {
+                config_handle.warning(warning.clone());
+            }
+        }
+        self.config_warnings = warnings;
+    }
+
+    /// Tells subscribers why the config was rejected, the message is kept
+    /// for clients subscribing later on.
+    pub fn config_error(&mut self, message: String) {
+        for config_handle in self.config_handles.iter() {
+            config_handle.error(message.clone());
+        }
+        self.config_error = Some(message);
+    }
+}
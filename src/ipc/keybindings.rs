@@ -0,0 +1,45 @@
+use smithay::reexports::wayland_server::Dispatch;
+
+use crate::config::KeyPattern;
+
+use super::{generated::keybindings::Keybindings, MagmaIpcManager, MagmaIpcHandler};
+
+impl<D> Dispatch<Keybindings, (), D> for MagmaIpcManager
+where
+    D: Dispatch<Keybindings, ()>,
+    D: MagmaIpcHandler,
+    D: 'static, {
+    fn request(
+        _state: &mut D,
+        _client: &smithay::reexports::wayland_server::Client,
+        _resource: &Keybindings,
+        _request: <Keybindings as smithay::reexports::wayland_server::Resource>::Request,
+        _data: &(),
+        _dhandle: &smithay::reexports::wayland_server::DisplayHandle,
+        _data_init: &mut smithay::reexports::wayland_server::DataInit<'_, D>,
+    ) {
+
+    }
+}
+
+impl MagmaIpcManager {
+    /// Sends the name of the active mode, `None` being the default one.
+    pub fn update_active_mode(&mut self, mode: Option<&str>) {
+        for keybindings_handle in self.keybindings_handles.iter() {
+            keybindings_handle.active_mode(mode.unwrap_or("default").to_string());
+        }
+    }
+
+    /// Sends the keys of the started key sequence, empty once it ends.
+    pub fn update_pending_keys(&mut self, keys: &[KeyPattern]) {
+        let keys = display_keys(keys);
+        for keybindings_handle in self.keybindings_handles.iter() {
+            keybindings_handle.pending_keys(keys.clone());
+        }
+    }
+}
+
+/// Joins `keys` the way sequences are written in error messages.
+pub fn display_keys(keys: &[KeyPattern]) -> String {
+    keys.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
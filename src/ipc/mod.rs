@@ -8,14 +8,25 @@ pub mod generated {
 
     wayland_scanner::generate_server_code!("ipc.xml");
 }
+mod config;
+mod keybindings;
 mod workspaces;
+
 use smithay::reexports::wayland_server::{GlobalDispatch, Dispatch, DisplayHandle, Client, New, DataInit};
 
-use self::generated::{magma_ipc::{MagmaIpc, Request}, workspaces::Workspaces};
+use self::generated::{config::Config, keybindings::Keybindings, magma_ipc::{MagmaIpc, Request}, workspaces::Workspaces};
+
+pub use self::keybindings::display_keys;
 
 
 pub struct MagmaIpcManager {
     pub workspace_handles: Vec<Workspaces>,
+    pub config_handles: Vec<Config>,
+    pub keybindings_handles: Vec<Keybindings>,
+    /// Why the last config reload failed, if it did.
+    pub config_error: Option<String>,
+    /// Parts of the config the last reload could not apply.
+    pub config_warnings: Vec<String>,
 }
 
 impl MagmaIpcManager {
@@ -24,6 +35,8 @@ impl MagmaIpcManager {
         D: GlobalDispatch<MagmaIpc, ()>,
         D: Dispatch<MagmaIpc, ()>,
         D: Dispatch<Workspaces, ()>,
+        D: Dispatch<Config, ()>,
+        D: Dispatch<Keybindings, ()>,
         D: MagmaIpcHandler,
         D: 'static,
     {
@@ -31,6 +44,10 @@ impl MagmaIpcManager {
 
         Self {
             workspace_handles: Vec::new(),
+            config_handles: Vec::new(),
+            keybindings_handles: Vec::new(),
+            config_error: None,
+            config_warnings: Vec::new(),
         }
     }
 }
@@ -40,6 +57,8 @@ where
     D: GlobalDispatch<MagmaIpc, ()>,
     D: Dispatch<MagmaIpc, ()>,
     D: Dispatch<Workspaces, ()>,
+    D: Dispatch<Config, ()>,
+    D: Dispatch<Keybindings, ()>,
     D: MagmaIpcHandler,
     D: 'static,
 {
@@ -60,6 +79,8 @@ where
     D: GlobalDispatch<MagmaIpc, ()>,
     D: Dispatch<MagmaIpc, ()>,
     D: Dispatch<Workspaces, ()>,
+    D: Dispatch<Config, ()>,
+    D: Dispatch<Keybindings, ()>,
     D: MagmaIpcHandler,
     D: 'static,
 {
@@ -74,6 +95,8 @@ where
     ) {
         match request {
             Request::Workspaces { id } => state.register_workspace(data_init.init(id, ())),
+            Request::Config { id } => state.register_config(data_init.init(id, ())),
+            Request::Keybindings { id } => state.register_keybindings(data_init.init(id, ())),
         };
     }
 }
@@ -93,9 +116,19 @@ macro_rules! delegate_magma_ipc {
         smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
             $crate::ipc::generated::workspaces::Workspaces: ()
         ] => $crate::ipc::MagmaIpcManager);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::ipc::generated::config::Config: ()
+        ] => $crate::ipc::MagmaIpcManager);
+
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::ipc::generated::keybindings::Keybindings: ()
+        ] => $crate::ipc::MagmaIpcManager);
     };
 }
 
 pub trait MagmaIpcHandler {
     fn register_workspace(&mut self, workspace: Workspaces);
+    fn register_config(&mut self, config: Config);
+    fn register_keybindings(&mut self, keybindings: Keybindings);
 }
\ No newline at end of file
@@ -0,0 +1,8 @@
+pub mod backends;
+pub mod cli;
+pub mod config;
+pub mod handlers;
+pub mod input;
+pub mod ipc;
+pub mod state;
+pub mod utils;
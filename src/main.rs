@@ -1,49 +1,128 @@
-use state::{CalloopData, MagmaState};
-use tracing::{error, info};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
 
-use crate::backends::{udev, winit};
-
-mod backends;
-mod config;
-mod handlers;
-mod input;
-mod ipc;
-mod state;
-mod utils;
+use magma::{
+    backends::{headless, udev, winit},
+    cli::Args,
+    config::{Config, DEFAULT_CONFIG},
+};
 
 static POSSIBLE_BACKENDS: &[&str] = &[
     "--winit : Run magma as a X11 or Wayland client using winit.",
     "--tty-udev : Run magma as a tty udev client (requires root if without logind).",
+    "--headless : Run magma without a display, rendering virtual outputs in software.",
+];
+static OTHER_OPTIONS: &[&str] = &[
+    "--config <path> : Use the config file at <path>.",
+    "--socket <name> : Listen on the wayland socket <name> instead of the next free one.",
+    "--log <filter> : Log filter in RUST_LOG syntax, e.g. \"magma=debug\".",
+    "--check-config, check-config : Check the config file for errors and exit.",
+    "--print-default-config : Print the built-in default config.",
+    "--write-default-config : Write the default config to $XDG_CONFIG_HOME/magma/config.ron.",
+    "--version : Print the version and exit.",
 ];
 fn main() {
-    if let Ok(env_filter) = tracing_subscriber::EnvFilter::try_from_default_env() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!();
+            print_usage();
+            std::process::exit(2);
+        }
+    };
+
+    let env_filter = match &args.log {
+        Some(filter) => match EnvFilter::try_new(filter) {
+            Ok(env_filter) => Some(env_filter),
+            Err(err) => {
+                eprintln!("Invalid log filter \"{}\": {}", filter, err);
+                std::process::exit(2);
+            }
+        },
+        None => EnvFilter::try_from_default_env().ok(),
+    };
+    if let Some(env_filter) = env_filter {
         tracing_subscriber::fmt().with_env_filter(env_filter).init();
     } else {
         tracing_subscriber::fmt().init();
     }
 
-    let arg = ::std::env::args().nth(1);
-    match arg.as_ref().map(|s| &s[..]) {
+    if args.version {
+        println!("magma {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+    if args.print_default_config {
+        print!("{}", DEFAULT_CONFIG);
+        return;
+    }
+    if args.write_default_config {
+        match Config::write_default() {
+            Ok(path) => println!("Wrote the default config to {}", path.display()),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if args.check_config {
+        std::process::exit(check_config(&args));
+    }
+
+    match args.backend.as_deref() {
         Some("--winit") => {
             info!("Starting magmawn with winit backend");
-            winit::init_winit();
+            winit::init_winit(&args);
         }
         Some("--tty-udev") => {
             info!("Starting magma on a tty using udev");
-            udev::init_udev();
+            udev::init_udev(&args);
         }
-        Some(other) => {
-            error!("Unknown backend: {}", other);
+        Some("--headless") => {
+            info!("Starting magma with headless backend");
+            headless::init_headless(&args);
         }
-        None => {
-            println!("USAGE: magma --backend");
-            println!();
-            println!("Possible backends are:");
-            for b in POSSIBLE_BACKENDS {
-                println!("\t{}", b);
-            }
+        _ => {
+            print_usage();
+            return;
         }
     }
 
     info!("Magma is shutting down");
 }
+
+/// Parses and validates the config file, returning the exit code.
+fn check_config(args: &Args) -> i32 {
+    let Some(path) = args.config.clone().or_else(Config::path) else {
+        println!("No config file found, the default config would be used");
+        return 0;
+    };
+    match Config::from_path(&path) {
+        Ok(config) => {
+            for warning in config.unmatched_outputs() {
+                println!("warning: {}", warning);
+            }
+            println!("{}: OK", path.display());
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn print_usage() {
+    println!("USAGE: magma --backend [options]");
+    println!();
+    println!("Possible backends are:");
+    for b in POSSIBLE_BACKENDS {
+        println!("\t{}", b);
+    }
+    println!();
+    println!("Other options are:");
+    for o in OTHER_OPTIONS {
+        println!("\t{}", o);
+    }
+}
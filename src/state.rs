@@ -1,10 +1,10 @@
-use std::{ffi::OsString, os::fd::AsRawFd, sync::Arc, time::Instant};
+use std::{collections::HashMap, ffi::OsString, os::fd::AsRawFd, path::{Path, PathBuf}, sync::Arc, time::Instant};
 
 use smithay::{
     desktop::{Window, PopupManager, layer_map_for_output},
-    input::{Seat, SeatState, keyboard::XkbConfig},
+    input::{Seat, SeatState, keyboard::{xkb, XkbConfig}},
     reexports::{
-        calloop::{generic::Generic, Interest, LoopSignal, Mode, PostAction, LoopHandle},
+        calloop::{generic::Generic, Interest, LoopSignal, Mode, PostAction, LoopHandle, RegistrationToken},
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
             Display, DisplayHandle,
@@ -18,11 +18,14 @@ use smithay::{
         shell::{xdg::{decoration::XdgDecorationState, XdgShellState}, wlr_layer::{WlrLayerShellState, Layer as WlrLayer}},
         shm::ShmState,
         socket::ListeningSocketSource, primary_selection::PrimarySelectionState,
+        pointer_gestures::PointerGesturesState,
+        tablet_manager::TabletManagerState,
     },
 };
-use tracing::warn;
+use inotify::{Inotify, WatchMask};
+use tracing::{error, info, warn};
 
-use crate::{config::Config, utils::{workspaces::Workspaces, focus::FocusTarget}, ipc::{MagmaIpcManager, MagmaIpcHandler}, delegate_magma_ipc};
+use crate::{cli::Args, handlers::xdg_shell::PendingWindow, config::{Config, ConfigError, KeyPattern, OutputConfig}, input::SwipeGesture, utils::{workspaces::{output_geometry, Workspaces}, focus::FocusTarget, tiling::{new_layout, update_layout}}, ipc::{MagmaIpcManager, MagmaIpcHandler, display_keys, generated::{config::Config as ConfigHandle, keybindings::Keybindings as KeybindingsHandle}}, delegate_magma_ipc};
 
 pub struct CalloopData<BackendData: Backend + 'static> {
     pub state: MagmaState<BackendData>,
@@ -31,6 +34,23 @@ pub struct CalloopData<BackendData: Backend + 'static> {
 
 pub trait Backend {
     fn seat_name(&self) -> String;
+
+    /// Problems that would keep `outputs` from being applied, like modes the
+    /// connected outputs don't support.
+    fn check_output_config(&self, _outputs: &HashMap<String, OutputConfig>) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Switches outputs to the modes configured for them, returning what failed.
+    fn apply_output_config(&mut self, _outputs: &HashMap<String, OutputConfig>) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Applies the `input` section of the config to the connected input devices,
+    /// returning the settings the devices refused.
+    fn apply_input_config(&mut self, _config: &Config) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub struct MagmaState<BackendData: Backend + 'static> {
@@ -38,11 +58,15 @@ pub struct MagmaState<BackendData: Backend + 'static> {
     pub backend_data: BackendData,
     pub loop_handle: LoopHandle<'static, CalloopData<BackendData>>,
     pub config: Config,
+    /// The file `config` was read from, reloads read it again.
+    pub config_path: Option<PathBuf>,
     pub start_time: Instant,
     pub socket_name: OsString,
     pub seat_name: String,
     pub loop_signal: LoopSignal,
     pub workspaces: Workspaces,
+    /// Toplevels waiting for their initial commit, which decides whether they float or are tiled.
+    pub pending_windows: Vec<PendingWindow>,
 
     pub compositor_state: CompositorState,
     pub xdg_shell_state: XdgShellState,
@@ -54,9 +78,35 @@ pub struct MagmaState<BackendData: Backend + 'static> {
     pub primary_selection_state: PrimarySelectionState,
     pub popup_manager: PopupManager,
     pub layer_shell_state: WlrLayerShellState,
+    pub pointer_gestures_state: PointerGesturesState,
+    pub tablet_manager_state: TabletManagerState,
     pub seat: Seat<Self>,
 
     pub pointer_location: Point<f64, Logical>,
+    /// The active keybinding mode, `None` for the default keybindings.
+    pub mode: Option<String>,
+    /// Keys of a started key sequence, waiting for the next one.
+    pub pending_keys: Vec<KeyPattern>,
+    pub sequence_timer: Option<RegistrationToken>,
+    /// Pressed keys that triggered or continued a binding, their releases are
+    /// not forwarded either.
+    pub suppressed_keys: Vec<u32>,
+    /// The last key pressed, release bindings only trigger for it.
+    pub last_pressed_key: Option<u32>,
+    /// Timer repeating the action of a held key.
+    pub key_repeat: Option<(u32, RegistrationToken)>,
+    /// Pressed buttons that triggered a binding.
+    pub suppressed_buttons: Vec<u32>,
+    /// Continuous scrolling not yet used up by a scroll binding.
+    pub scroll_remainder: (f64, f64),
+    /// Touchpad swipe bound to an action, kept from clients.
+    pub swipe_gesture: Option<SwipeGesture>,
+    /// Keymap of the first layout, if keysym bindings are matched against it.
+    pub base_keymap: Option<xkb::Keymap>,
+    /// Name of the keyboard keys were last pressed on.
+    pub active_keyboard: Option<String>,
+    /// Key repeat delay and rate of the seat's keyboard.
+    pub key_repeat_info: (i32, i32),
 
     pub ipc_manager: MagmaIpcManager,
 }
@@ -67,12 +117,20 @@ impl<BackendData: Backend> MagmaState<BackendData> {
         loop_signal: LoopSignal,
         display: &mut Display<MagmaState<BackendData>>,
         backend_data: BackendData,
+        args: &Args,
     ) -> Self {
         let start_time = Instant::now();
 
         let dh = display.handle();
 
-        let config = Config::load();
+        let config_path = args.config.clone().or_else(Config::path);
+        let config = config_path
+            .as_deref()
+            .map_or_else(|| Ok(Config::default_config()), Config::from_path)
+            .unwrap_or_else(|err| {
+                error!("{}", err);
+                std::process::exit(1)
+            });
 
         let compositor_state = CompositorState::new::<Self>(&dh);
         let xdg_shell_state = XdgShellState::new::<Self>(&dh);
@@ -83,22 +141,32 @@ impl<BackendData: Backend> MagmaState<BackendData> {
         let data_device_state = DataDeviceState::new::<Self>(&dh);
         let primary_selection_state = PrimarySelectionState::new::<Self>(&dh);
         let layer_shell_state = WlrLayerShellState::new::<Self>(&dh);
+        let pointer_gestures_state = PointerGesturesState::new::<Self>(&dh);
+        let tablet_manager_state = TabletManagerState::new::<Self>(&dh);
         let seat_name = backend_data.seat_name();
         let mut seat = seat_state.new_wl_seat(&dh, seat_name.clone());
         let conf = config.xkb.clone();
-        if let Err(err) = seat.add_keyboard((&conf).into(), 200, 25) {
+        let key_repeat_info = config.input_kind_config(&["keyboard"]).repeat_info();
+        let (repeat_delay, repeat_rate) = key_repeat_info;
+        if let Err(err) = seat.add_keyboard((&conf).into(), repeat_delay, repeat_rate) {
             warn!(
                 ?err,
                 "Failed to load provided xkb config. Trying default...",
             );
-            seat.add_keyboard(XkbConfig::default(), 200, 25)
+            seat.add_keyboard(XkbConfig::default(), repeat_delay, repeat_rate)
                 .expect("Failed to load xkb configuration files");
         }
         seat.add_pointer();
+        seat.add_touch();
 
-        let workspaces = Workspaces::new(config.workspaces);
+        let base_keymap = base_keymap(&config);
+        let workspaces = Workspaces::new(&config);
 
-        let socket_name = Self::init_wayland_listener(&mut loop_handle, display);
+        let socket_name =
+            Self::init_wayland_listener(&mut loop_handle, display, args.socket.as_deref());
+        if let Some(path) = &config_path {
+            Self::init_config_watcher(&mut loop_handle, path);
+        }
 
         let ipc_manager = MagmaIpcManager::new::<Self>(&dh);
 
@@ -107,10 +175,12 @@ impl<BackendData: Backend> MagmaState<BackendData> {
             dh,
             backend_data,
             config,
+            config_path,
             start_time,
             seat_name,
             socket_name,
             workspaces,
+            pending_windows: Vec::new(),
             compositor_state,
             xdg_shell_state,
             xdg_decoration_state,
@@ -121,8 +191,22 @@ impl<BackendData: Backend> MagmaState<BackendData> {
             data_device_state,
             primary_selection_state,
             layer_shell_state,
+            pointer_gestures_state,
+            tablet_manager_state,
             seat,
             pointer_location: Point::from((0.0, 0.0)),
+            mode: None,
+            pending_keys: Vec::new(),
+            sequence_timer: None,
+            suppressed_keys: Vec::new(),
+            last_pressed_key: None,
+            key_repeat: None,
+            suppressed_buttons: Vec::new(),
+            scroll_remainder: (0.0, 0.0),
+            swipe_gesture: None,
+            base_keymap,
+            active_keyboard: None,
+            key_repeat_info,
             popup_manager: PopupManager::default(),
             ipc_manager,
         }
@@ -130,9 +214,18 @@ impl<BackendData: Backend> MagmaState<BackendData> {
     fn init_wayland_listener(
         handle: &mut LoopHandle<'static, CalloopData<BackendData>>,
         display: &mut Display<MagmaState<BackendData>>,
+        socket_name: Option<&str>,
     ) -> OsString {
-        // Creates a new listening socket, automatically choosing the next available `wayland` socket name.
-        let listening_socket = ListeningSocketSource::new_auto().unwrap();
+        // Creates a new listening socket, automatically choosing the next available `wayland` socket name
+        // unless one was given.
+        let listening_socket = match socket_name {
+            Some(name) => ListeningSocketSource::with_name(name),
+            None => ListeningSocketSource::new_auto(),
+        }
+        .unwrap_or_else(|err| {
+            error!("Failed to create the wayland socket: {}", err);
+            std::process::exit(1)
+        });
 
         // Get the name of the listening socket.
         // Clients will connect to this socket.
@@ -169,34 +262,187 @@ impl<BackendData: Backend> MagmaState<BackendData> {
         socket_name
     }
 
+    /// Reloads the config whenever its file is written or replaced.
+    ///
+    /// The parent directory is watched, as editors tend to save by renaming
+    /// a temporary file over the original.
+    fn init_config_watcher(
+        handle: &mut LoopHandle<'static, CalloopData<BackendData>>,
+        path: &Path,
+    ) {
+        let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+        let file_name = file_name.to_os_string();
+
+        let mut inotify = match Inotify::init() {
+            Ok(inotify) => inotify,
+            Err(err) => {
+                warn!("Failed to watch the config file: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = inotify.add_watch(
+            dir,
+            WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+        ) {
+            warn!("Failed to watch {}: {}", dir.display(), err);
+            return;
+        }
+
+        handle
+            .insert_source(
+                Generic::new(inotify, Interest::READ, Mode::Level),
+                move |_, inotify, data| {
+                    let mut buffer = [0; 1024];
+                    let changed = match inotify.read_events(&mut buffer) {
+                        Ok(mut events) => {
+                            events.any(|event| event.name == Some(file_name.as_os_str()))
+                        }
+                        Err(_) => false,
+                    };
+                    if changed {
+                        info!("Config file changed, reloading");
+                        data.state.reload_config();
+                    }
+                    Ok(PostAction::Continue)
+                },
+            )
+            .expect("Failed to init the config watcher.");
+    }
+
+    /// Reads the config file again and applies it.
+    ///
+    /// Either everything is applied or, if the new config is invalid or can't
+    /// be applied, nothing is and the error is logged and sent to IPC clients.
+    /// Parts the backend still fails to apply are reported as warnings.
+    pub fn reload_config(&mut self) {
+        // a config file may have been created since startup
+        let path = self.config_path.clone().or_else(Config::path);
+        let result = match &path {
+            Some(path) => Config::from_path(path),
+            None => Ok(Config::default_config()),
+        };
+        let config = match result {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Keeping the current config: {}", err);
+                self.ipc_manager.config_error(err.to_string());
+                return;
+            }
+        };
+
+        // everything that can be checked up front is, before anything changes
+        let mut problems = Vec::new();
+        if config.workspaces != self.config.workspaces {
+            problems.push(format!(
+                "changing the number of workspaces from {} to {} requires a restart",
+                self.config.workspaces, config.workspaces
+            ));
+        }
+        let xkb_changed = config.xkb != self.config.xkb;
+        if xkb_changed && config.xkb.keymap().is_none() {
+            problems.push("the xkb settings don't compile to a keymap".to_string());
+        }
+        problems.extend(self.backend_data.check_output_config(&config.outputs));
+        if !problems.is_empty() {
+            let err = ConfigError::Invalid(problems);
+            error!("Keeping the current config: {}", err);
+            self.ipc_manager.config_error(err.to_string());
+            return;
+        }
+
+        let mut warnings = Vec::new();
+        if xkb_changed {
+            let keyboard = self.seat.get_keyboard().unwrap();
+            if let Err(err) = keyboard.set_xkb_config(self, (&config.xkb).into()) {
+                warnings.push(format!("failed to apply the xkb settings: {:?}", err));
+            }
+        }
+        self.base_keymap = base_keymap(&config);
+
+        let connected: Vec<String> = self.workspaces.outputs().map(|o| o.name()).collect();
+        for name in config.unknown_outputs(&connected) {
+            warnings.push(format!("output {} is not connected", name));
+        }
+
+        warnings.extend(self.backend_data.apply_output_config(&config.outputs));
+        warnings.extend(self.backend_data.apply_input_config(&config));
+        for output in self.workspaces.outputs() {
+            layer_map_for_output(output).arrange();
+        }
+        let master_changed = (config.master_count, config.master_factor)
+            != (self.config.master_count, self.config.master_factor);
+        for (id, workspace) in self.workspaces.iter().enumerate() {
+            // layouts switched to at runtime survive reloads that leave them be
+            let kind = config.workspace_layout(id as u8);
+            if kind != self.config.workspace_layout(id as u8) {
+                workspace.set_layout(new_layout(kind, &config));
+            } else {
+                let layout = workspace.layout_mut();
+                if master_changed {
+                    layout.set_master(config.master_count, config.master_factor);
+                }
+                if config.column_width != self.config.column_width {
+                    layout.set_column_width(config.column_width);
+                }
+            }
+            update_layout(workspace, config.gaps);
+        }
+
+        self.cancel_key_sequence();
+        self.config = config;
+        self.config_path = path;
+        if self.mode.as_ref().map_or(false, |mode| !self.config.modes.contains_key(mode)) {
+            self.set_mode(None);
+        }
+        if let Some(keyboard) = self.active_keyboard.clone() {
+            self.set_active_keyboard(&keyboard);
+        }
+        info!("Config reloaded");
+        for warning in warnings.iter() {
+            warn!("Not applied: {}", warning);
+        }
+        self.ipc_manager.config_reloaded(warnings);
+    }
+
     pub fn window_under(&mut self) -> Option<(Window, Point<i32, Logical>)> {
         let pos = self.pointer_location;
+        let output = self.workspaces.output_under(pos)?;
         self.workspaces
-            .current()
+            .active_on(output)?
             .window_under(pos)
             .map(|(w, p)| (w.clone(), p))
     }
     pub fn surface_under(&self) -> Option<(FocusTarget, Point<i32, Logical>)> {
-        let pos = self.pointer_location;
-        let output = self.workspaces.current().outputs().find(|o| {
-            let geometry = self.workspaces.current().output_geometry(o).unwrap();
-            geometry.contains(pos.to_i32_round())
-        })?;
-        let output_geo = self.workspaces.current().output_geometry(output).unwrap();
+        self.surface_at(self.pointer_location)
+    }
+
+    /// The surface at `pos` and its location, like [`Self::surface_under`] the pointer.
+    pub fn surface_at(&self, pos: Point<f64, Logical>) -> Option<(FocusTarget, Point<i32, Logical>)> {
+        let output = self.workspaces.output_under(pos)?;
+        let output_geo = output_geometry(output)?;
+        let workspace = self.workspaces.active_on(output);
         let layers = layer_map_for_output(output);
+        // layers are positioned relative to their output
+        let layer_pos = pos - output_geo.loc.to_f64();
 
         let mut under = None;
-        if let Some(layer) = layers
-            .layer_under(WlrLayer::Overlay, pos)
-            .or_else(|| layers.layer_under(WlrLayer::Top, pos))
+        if let Some(layer) = layers.layer_under(WlrLayer::Overlay, layer_pos) {
+            let layer_loc = layers.layer_geometry(layer).unwrap().loc;
+            under = Some((layer.clone().into(), output_geo.loc + layer_loc))
+        } else if let Some((window, location)) =
+            workspace.and_then(|w| w.fullscreen_window_under(pos))
         {
+            under = Some((window.clone().into(), location));
+        } else if let Some(layer) = layers.layer_under(WlrLayer::Top, layer_pos) {
             let layer_loc = layers.layer_geometry(layer).unwrap().loc;
             under = Some((layer.clone().into(), output_geo.loc + layer_loc))
-        } else if let Some((window, location)) = self.workspaces.current().window_under(pos) {
+        } else if let Some((window, location)) = workspace.and_then(|w| w.window_under(pos)) {
             under = Some((window.clone().into(), location));
         } else if let Some(layer) = layers
-            .layer_under(WlrLayer::Bottom, pos)
-            .or_else(|| layers.layer_under(WlrLayer::Background, pos))
+            .layer_under(WlrLayer::Bottom, layer_pos)
+            .or_else(|| layers.layer_under(WlrLayer::Background, layer_pos))
         {
             let layer_loc = layers.layer_geometry(layer).unwrap().loc;
             under = Some((layer.clone().into(), output_geo.loc + layer_loc));
@@ -219,4 +465,32 @@ impl<BackendData: Backend> MagmaIpcHandler for MagmaState<BackendData> {
         self.ipc_manager.update_active_workspace(self.workspaces.current.into());
         self.ipc_manager.update_occupied_workspaces(&mut self.workspaces);
     }
-}
\ No newline at end of file
+
+    fn register_config(&mut self, config: ConfigHandle) {
+        if let Some(message) = &self.ipc_manager.config_error {
+            config.error(message.clone());
+        }
+        for warning in self.ipc_manager.config_warnings.iter() {
+            config.warning(warning.clone());
+        }
+        self.ipc_manager.config_handles.push(config);
+    }
+
+    fn register_keybindings(&mut self, keybindings: KeybindingsHandle) {
+        keybindings.active_mode(self.mode.clone().unwrap_or_else(|| "default".to_string()));
+        keybindings.pending_keys(display_keys(&self.pending_keys));
+        self.ipc_manager.keybindings_handles.push(keybindings);
+    }
+}
+
+/// The keymap keysym bindings are matched against with `bind_base_layout`.
+fn base_keymap(config: &Config) -> Option<xkb::Keymap> {
+    if !config.bind_base_layout {
+        return None;
+    }
+    let keymap = config.xkb.keymap();
+    if keymap.is_none() {
+        warn!("Failed to compile the xkb keymap, matching bindings against the active layout");
+    }
+    keymap
+}
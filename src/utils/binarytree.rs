@@ -1,13 +1,21 @@
-use smithay::desktop::Window;
+use smithay::{desktop::Window, utils::{Logical, Rectangle}};
 use std::fmt::Debug;
 use std::{cell::RefCell, rc::Rc};
 
-use super::workspaces::MagmaWindow;
+use crate::config::{Direction, Flip};
+
+use super::{group::Group, tiling::split_area, workspaces::MagmaWindow};
+
+/// Bounds for the ratio of a split, so neither side collapses.
+pub const MIN_RATIO: f32 = 0.1;
+pub const MAX_RATIO: f32 = 0.9;
 
 #[derive(Clone)]
 pub enum BinaryTree {
     Empty,
     Window(Rc<RefCell<MagmaWindow>>),
+    /// Windows sharing a leaf, see [`Group`].
+    Group(Group),
     Split {
         split: HorizontalOrVertical,
         ratio: f32,
@@ -21,6 +29,10 @@ impl Debug for BinaryTree {
         match self {
             Self::Empty => write!(f, "Empty"),
             Self::Window(w) => w.borrow().rec.fmt(f),
+            Self::Group(group) => f
+                .debug_list()
+                .entries(group.windows.iter().map(|w| w.borrow().rec))
+                .finish(),
             Self::Split {
                 left,
                 right,
@@ -43,6 +55,15 @@ pub enum HorizontalOrVertical {
     Vertical,
 }
 
+impl From<Direction> for HorizontalOrVertical {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Left | Direction::Right => HorizontalOrVertical::Horizontal,
+            Direction::Up | Direction::Down => HorizontalOrVertical::Vertical,
+        }
+    }
+}
+
 impl BinaryTree {
     pub fn new() -> Self {
         BinaryTree::Empty
@@ -58,9 +79,10 @@ impl BinaryTree {
             BinaryTree::Empty => {
                 *self = BinaryTree::Window(window);
             }
-            BinaryTree::Window(w) => {
+            BinaryTree::Window(_) | BinaryTree::Group(_) => {
+                let leaf = std::mem::replace(self, BinaryTree::Empty);
                 *self = BinaryTree::Split {
-                    left: Box::new(BinaryTree::Window(w.clone())),
+                    left: Box::new(leaf),
                     right: Box::new(BinaryTree::Window(window)),
                     split: splitnew,
                     ratio: rationew,
@@ -86,6 +108,13 @@ impl BinaryTree {
                     *self = BinaryTree::Empty;
                 }
             }
+            BinaryTree::Group(group) => {
+                group.remove(window);
+                // a single window doesn't need a group
+                if group.windows.len() == 1 {
+                    *self = BinaryTree::Window(group.windows[0].clone());
+                }
+            }
             BinaryTree::Split {
                 left,
                 right,
@@ -110,10 +139,287 @@ impl BinaryTree {
         }
     }
 
+    /// Exchanges the leaves holding `a` and `b`.
+    pub fn swap(&mut self, a: &Rc<RefCell<MagmaWindow>>, b: &Rc<RefCell<MagmaWindow>>) {
+        match self {
+            BinaryTree::Empty => {}
+            BinaryTree::Window(w) => {
+                if Rc::ptr_eq(w, a) {
+                    *w = b.clone();
+                } else if Rc::ptr_eq(w, b) {
+                    *w = a.clone();
+                }
+            }
+            BinaryTree::Group(group) => {
+                for w in group.windows.iter_mut() {
+                    if Rc::ptr_eq(w, a) {
+                        *w = b.clone();
+                    } else if Rc::ptr_eq(w, b) {
+                        *w = a.clone();
+                    }
+                }
+            }
+            BinaryTree::Split { left, right, .. } => {
+                left.swap(a, b);
+                right.swap(a, b);
+            }
+        }
+    }
+
+    /// Replaces the leaf holding `target` with a split of that leaf and `window`.
+    /// `window` becomes the left child if `first` is set, the right one otherwise.
+    ///
+    /// Returns `false` if `target` is not part of the tree.
+    pub fn insert_next_to(
+        &mut self,
+        target: &Window,
+        window: Rc<RefCell<MagmaWindow>>,
+        splitnew: HorizontalOrVertical,
+        rationew: f32,
+        first: bool,
+    ) -> bool {
+        match self {
+            BinaryTree::Empty => false,
+            BinaryTree::Window(_) | BinaryTree::Group(_) => {
+                if !self.contains(target) {
+                    return false;
+                }
+                let leaf = Box::new(std::mem::replace(self, BinaryTree::Empty));
+                let window = Box::new(BinaryTree::Window(window));
+                let (left, right) = if first { (window, leaf) } else { (leaf, window) };
+                *self = BinaryTree::Split {
+                    left,
+                    right,
+                    split: splitnew,
+                    ratio: rationew,
+                };
+                true
+            }
+            BinaryTree::Split { left, right, .. } => {
+                left.insert_next_to(target, window.clone(), splitnew, rationew, first)
+                    || right.insert_next_to(target, window, splitnew, rationew, first)
+            }
+        }
+    }
+
+    pub fn contains(&self, window: &Window) -> bool {
+        match self {
+            BinaryTree::Empty => false,
+            BinaryTree::Window(w) => w.borrow().window == *window,
+            BinaryTree::Group(group) => group.contains(window),
+            BinaryTree::Split { left, right, .. } => left.contains(window) || right.contains(window),
+        }
+    }
+
+    /// Moves the boundary on the `direction` side of `window` by `amount` pixels,
+    /// growing the window for positive amounts.
+    ///
+    /// The nearest ancestor split on the matching axis, having `window` on the
+    /// opposite side of the boundary, is adjusted. `area` is the area covered by `self`.
+    /// Returns `false` if no such split exists.
+    pub fn resize(
+        &mut self,
+        window: &Window,
+        direction: Direction,
+        amount: f32,
+        area: Rectangle<i32, Logical>,
+    ) -> bool {
+        if let BinaryTree::Split {
+            split,
+            ratio,
+            left,
+            right,
+        } = self
+        {
+            let (left_area, right_area) = split_area(area, *split, *ratio);
+            let in_left = left.contains(window);
+            if !in_left && !right.contains(window) {
+                return false;
+            }
+            if in_left && left.resize(window, direction, amount, left_area) {
+                return true;
+            }
+            if !in_left && right.resize(window, direction, amount, right_area) {
+                return true;
+            }
+
+            if *split != HorizontalOrVertical::from(direction) {
+                return false;
+            }
+            let size = match split {
+                HorizontalOrVertical::Horizontal => area.size.w,
+                HorizontalOrVertical::Vertical => area.size.h,
+            } as f32;
+            let delta = match direction {
+                Direction::Right | Direction::Down if in_left => amount / size,
+                Direction::Left | Direction::Up if !in_left => -amount / size,
+                _ => return false,
+            };
+            *ratio = (*ratio + delta).clamp(MIN_RATIO, MAX_RATIO);
+            return true;
+        }
+        false
+    }
+
+    /// Number of leaves in the tree, a group counting as one.
+    pub fn leaves(&self) -> usize {
+        match self {
+            BinaryTree::Empty => 0,
+            BinaryTree::Window(_) | BinaryTree::Group(_) => 1,
+            BinaryTree::Split { left, right, .. } => left.leaves() + right.leaves(),
+        }
+    }
+
+    /// Sets the ratios so every window gets the same area.
+    ///
+    /// Unlike resizing, this isn't bound to `MIN_RATIO..MAX_RATIO`, a side
+    /// with a single window next to ten others gets less than a tenth.
+    pub fn balance(&mut self) {
+        if let BinaryTree::Split {
+            ratio, left, right, ..
+        } = self
+        {
+            left.balance();
+            right.balance();
+            let total = left.leaves() + right.leaves();
+            if total > 0 {
+                *ratio = left.leaves() as f32 / total as f32;
+            }
+        }
+    }
+
+    /// Splits every area in half.
+    pub fn equalize(&mut self) {
+        if let BinaryTree::Split {
+            ratio, left, right, ..
+        } = self
+        {
+            *ratio = 0.5;
+            left.equalize();
+            right.equalize();
+        }
+    }
+
+    /// Rotates the tree clockwise by `degrees`, which is 90, 180 or 270.
+    pub fn rotate(&mut self, degrees: u16) {
+        if let BinaryTree::Split {
+            split,
+            ratio,
+            left,
+            right,
+        } = self
+        {
+            // whether the first child ends up on the right or at the bottom
+            let swap = match degrees {
+                90 => *split == HorizontalOrVertical::Vertical,
+                180 => true,
+                270 => *split == HorizontalOrVertical::Horizontal,
+                _ => return,
+            };
+            if swap {
+                std::mem::swap(left, right);
+                *ratio = 1.0 - *ratio;
+            }
+            if degrees != 180 {
+                *split = match split {
+                    HorizontalOrVertical::Horizontal => HorizontalOrVertical::Vertical,
+                    HorizontalOrVertical::Vertical => HorizontalOrVertical::Horizontal,
+                };
+            }
+            left.rotate(degrees);
+            right.rotate(degrees);
+        }
+    }
+
+    /// Mirrors the tree, exchanging the children of the splits along `flip`.
+    pub fn flip(&mut self, flip: Flip) {
+        if let BinaryTree::Split {
+            split,
+            ratio,
+            left,
+            right,
+        } = self
+        {
+            let axis = match flip {
+                Flip::Horizontal => HorizontalOrVertical::Horizontal,
+                Flip::Vertical => HorizontalOrVertical::Vertical,
+            };
+            if *split == axis {
+                std::mem::swap(left, right);
+                *ratio = 1.0 - *ratio;
+            }
+            left.flip(flip);
+            right.flip(flip);
+        }
+    }
+
+    /// Adds `window` to the leaf holding `target`, turning it into a group if
+    /// it only holds `target`. `window` becomes the active window of the group.
+    ///
+    /// Returns `false` if `target` is not part of the tree.
+    pub fn add_to_group(&mut self, target: &Window, window: Rc<RefCell<MagmaWindow>>) -> bool {
+        match self {
+            BinaryTree::Empty => false,
+            BinaryTree::Window(w) => {
+                if w.borrow().window != *target {
+                    return false;
+                }
+                *self = BinaryTree::Group(Group::new(vec![w.clone(), window], 1));
+                true
+            }
+            BinaryTree::Group(group) => {
+                if !group.contains(target) {
+                    return false;
+                }
+                group.windows.push(window);
+                group.active = group.windows.len() - 1;
+                true
+            }
+            BinaryTree::Split { left, right, .. } => {
+                left.add_to_group(target, window.clone()) || right.add_to_group(target, window)
+            }
+        }
+    }
+
+    /// The group holding `window`, if any.
+    pub fn group(&self, window: &Window) -> Option<&Group> {
+        match self {
+            BinaryTree::Group(group) if group.contains(window) => Some(group),
+            BinaryTree::Split { left, right, .. } => {
+                left.group(window).or_else(|| right.group(window))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn group_mut(&mut self, window: &Window) -> Option<&mut Group> {
+        match self {
+            BinaryTree::Group(group) if group.contains(window) => Some(group),
+            BinaryTree::Split { left, right, .. } => match left.group_mut(window) {
+                Some(group) => Some(group),
+                None => right.group_mut(window),
+            },
+            _ => None,
+        }
+    }
+
+    /// All groups of the tree.
+    pub fn groups(&self) -> Vec<&Group> {
+        match self {
+            BinaryTree::Group(group) => vec![group],
+            BinaryTree::Split { left, right, .. } => {
+                let mut groups = left.groups();
+                groups.extend(right.groups());
+                groups
+            }
+            _ => Vec::new(),
+        }
+    }
+
     pub fn next_split(&self) -> HorizontalOrVertical {
         match self {
             BinaryTree::Empty => HorizontalOrVertical::Horizontal,
-            BinaryTree::Window(_w) => HorizontalOrVertical::Horizontal,
+            BinaryTree::Window(_) | BinaryTree::Group(_) => HorizontalOrVertical::Horizontal,
             BinaryTree::Split {
                 left: _,
                 right,
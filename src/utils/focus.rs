@@ -4,7 +4,12 @@ pub use smithay::{
     desktop::{LayerSurface, PopupKind},
     input::{
         keyboard::{KeyboardTarget, KeysymHandle, ModifiersState},
-        pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget, RelativeMotionEvent},
+        pointer::{
+            AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent,
+            GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+            GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent, MotionEvent,
+            PointerTarget, RelativeMotionEvent,
+        },
         Seat,
     },
     reexports::wayland_server::{backend::ObjectId, protocol::wl_surface::WlSurface, Resource},
@@ -39,6 +44,27 @@ impl From<FocusTarget> for WlSurface {
     }
 }
 
+/// Implements the gesture methods of [`PointerTarget`] by passing them on to
+/// the wrapped window or surface.
+macro_rules! gestures_to_target {
+    ($($name:ident: $event:ty),* $(,)?) => {
+        $(
+            fn $name(
+                &self,
+                seat: &Seat<MagmaState<BackendData>>,
+                data: &mut MagmaState<BackendData>,
+                event: &$event,
+            ) {
+                match self {
+                    FocusTarget::Window(w) => PointerTarget::$name(w, seat, data, event),
+                    FocusTarget::LayerSurface(l) => PointerTarget::$name(l, seat, data, event),
+                    FocusTarget::Popup(p) => PointerTarget::$name(p.wl_surface(), seat, data, event),
+                }
+            }
+        )*
+    };
+}
+
 impl<BackendData: Backend> PointerTarget<MagmaState<BackendData>> for FocusTarget {
     fn enter(
         &self,
@@ -113,6 +139,16 @@ impl<BackendData: Backend> PointerTarget<MagmaState<BackendData>> for FocusTarge
             FocusTarget::Popup(p) => PointerTarget::leave(p.wl_surface(), seat, data, serial, time),
         }
     }
+    gestures_to_target!(
+        gesture_swipe_begin: GestureSwipeBeginEvent,
+        gesture_swipe_update: GestureSwipeUpdateEvent,
+        gesture_swipe_end: GestureSwipeEndEvent,
+        gesture_pinch_begin: GesturePinchBeginEvent,
+        gesture_pinch_update: GesturePinchUpdateEvent,
+        gesture_pinch_end: GesturePinchEndEvent,
+        gesture_hold_begin: GestureHoldBeginEvent,
+        gesture_hold_end: GestureHoldEndEvent,
+    );
 }
 
 impl<BackendData: Backend> KeyboardTarget<MagmaState<BackendData>> for FocusTarget {
@@ -0,0 +1,427 @@
+use smithay::{
+    desktop::Window,
+    input::pointer::{
+        AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent, GesturePinchBeginEvent,
+        GesturePinchEndEvent, GesturePinchUpdateEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
+        GestureSwipeUpdateEvent, GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab,
+        PointerInnerHandle, RelativeMotionEvent,
+    },
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::{ResizeEdge, State},
+    utils::{Logical, Point, Rectangle, Size},
+    wayland::{compositor::with_states, shell::xdg::SurfaceCachedState},
+};
+
+use crate::{
+    config::Direction,
+    state::{Backend, MagmaState},
+    utils::{focus::FocusTarget, tiling::update_layout},
+};
+
+/// Implements the gesture methods of [`PointerGrab`] by passing the gestures on
+/// unchanged, none of the grabs react to them.
+macro_rules! forward_gestures {
+    () => {
+        forward_gestures!(
+            gesture_swipe_begin: GestureSwipeBeginEvent,
+            gesture_swipe_update: GestureSwipeUpdateEvent,
+            gesture_swipe_end: GestureSwipeEndEvent,
+            gesture_pinch_begin: GesturePinchBeginEvent,
+            gesture_pinch_update: GesturePinchUpdateEvent,
+            gesture_pinch_end: GesturePinchEndEvent,
+            gesture_hold_begin: GestureHoldBeginEvent,
+            gesture_hold_end: GestureHoldEndEvent,
+        );
+    };
+    ($($name:ident: $event:ty),* $(,)?) => {
+        $(
+            fn $name(
+                &mut self,
+                data: &mut MagmaState<BackendData>,
+                handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+                event: &$event,
+            ) {
+                handle.$name(data, event)
+            }
+        )*
+    };
+}
+
+/// Resizes the splits around a tiled window while a button is held.
+///
+/// The boundaries closest to where the grab started are dragged along with the pointer.
+pub struct ResizeTileGrab<BackendData: Backend + 'static> {
+    pub start_data: PointerGrabStartData<MagmaState<BackendData>>,
+    pub window: Window,
+    pub edges: (Direction, Direction),
+    pub last_location: Point<f64, Logical>,
+}
+
+impl<BackendData: Backend> ResizeTileGrab<BackendData> {
+    pub fn start(
+        start_data: PointerGrabStartData<MagmaState<BackendData>>,
+        window: Window,
+        state: &mut MagmaState<BackendData>,
+    ) -> Option<Self> {
+        let rec = state
+            .workspaces
+            .workspace_from_window(&window)?
+            .magmawindows()
+            .find(|w| w.window == window)?
+            .rec;
+        let location = start_data.location;
+
+        let horizontal = if location.x < (rec.loc.x + rec.size.w / 2) as f64 {
+            Direction::Left
+        } else {
+            Direction::Right
+        };
+        let vertical = if location.y < (rec.loc.y + rec.size.h / 2) as f64 {
+            Direction::Up
+        } else {
+            Direction::Down
+        };
+
+        Some(ResizeTileGrab {
+            start_data,
+            window,
+            edges: (horizontal, vertical),
+            last_location: location,
+        })
+    }
+}
+
+impl<BackendData: Backend> PointerGrab<MagmaState<BackendData>> for ResizeTileGrab<BackendData> {
+    fn motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // no client has pointer focus while the grab is active
+        handle.motion(data, None, event);
+
+        let delta = event.location - self.last_location;
+        self.last_location = event.location;
+
+        let gaps = data.config.gaps;
+        if let Some(workspace) = data.workspaces.workspace_from_window(&self.window) {
+            for (edge, amount) in [(self.edges.0, delta.x), (self.edges.1, delta.y)] {
+                let amount = match edge {
+                    Direction::Right | Direction::Down => amount,
+                    Direction::Left | Direction::Up => -amount,
+                };
+                if amount != 0.0 {
+                    workspace.resize_window(&self.window, edge, amount as f32, gaps);
+                }
+            }
+        }
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            handle.unset_grab(data, event.serial, event.time);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    forward_gestures!();
+
+    fn start_data(&self) -> &PointerGrabStartData<MagmaState<BackendData>> {
+        &self.start_data
+    }
+}
+
+/// Moves a floating window along with the pointer while a button is held.
+///
+/// Tiled windows are taken out of the layout and float from where they were.
+pub struct MoveSurfaceGrab<BackendData: Backend + 'static> {
+    pub start_data: PointerGrabStartData<MagmaState<BackendData>>,
+    pub window: Window,
+    pub initial_window_location: Point<i32, Logical>,
+}
+
+impl<BackendData: Backend> MoveSurfaceGrab<BackendData> {
+    pub fn start(
+        start_data: PointerGrabStartData<MagmaState<BackendData>>,
+        window: Window,
+        state: &mut MagmaState<BackendData>,
+    ) -> Option<Self> {
+        let gaps = state.config.gaps;
+        let workspace = state.workspaces.workspace_from_window(&window)?;
+        if workspace.is_fullscreen(&window) || workspace.is_maximized(&window) {
+            return None;
+        }
+        if !workspace.is_floating(&window) {
+            let rec = workspace
+                .magmawindows()
+                .find(|w| w.window == window)
+                .map(|w| w.rec)?;
+            workspace.float_window(&window, Some(rec.size));
+            // float from where the window was tiled, not from its last floating geometry
+            workspace.set_floating_geometry(&window, rec);
+            window.toplevel().with_pending_state(|state| {
+                state.size = Some(rec.size);
+            });
+            window.toplevel().send_configure();
+            update_layout(workspace, gaps);
+        }
+        let initial_window_location = workspace.floating_geometry(&window)?.loc;
+
+        Some(MoveSurfaceGrab {
+            start_data,
+            window,
+            initial_window_location,
+        })
+    }
+}
+
+impl<BackendData: Backend> PointerGrab<MagmaState<BackendData>> for MoveSurfaceGrab<BackendData> {
+    fn motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // no client has pointer focus while the grab is active
+        handle.motion(data, None, event);
+
+        let delta = event.location - self.start_data.location;
+        let location = self.initial_window_location.to_f64() + delta;
+
+        if let Some(workspace) = data.workspaces.workspace_from_window(&self.window) {
+            if let Some(mut rec) = workspace.floating_geometry(&self.window) {
+                rec.loc = location.to_i32_round();
+                workspace.set_floating_geometry(&self.window, rec);
+            }
+        }
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            handle.unset_grab(data, event.serial, event.time);
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    forward_gestures!();
+
+    fn start_data(&self) -> &PointerGrabStartData<MagmaState<BackendData>> {
+        &self.start_data
+    }
+}
+
+/// Resizes a floating window by dragging one of its edges or corners.
+///
+/// The client is told it is being resized and receives a configure for every motion.
+pub struct ResizeSurfaceGrab<BackendData: Backend + 'static> {
+    pub start_data: PointerGrabStartData<MagmaState<BackendData>>,
+    pub window: Window,
+    pub edges: ResizeEdge,
+    pub initial_rec: Rectangle<i32, Logical>,
+}
+
+impl<BackendData: Backend> ResizeSurfaceGrab<BackendData> {
+    /// Starts resizing `edges` of a floating window, or the edges closest
+    /// to the pointer if `None`.
+    pub fn start(
+        start_data: PointerGrabStartData<MagmaState<BackendData>>,
+        window: Window,
+        edges: Option<ResizeEdge>,
+        state: &mut MagmaState<BackendData>,
+    ) -> Option<Self> {
+        let workspace = state.workspaces.workspace_from_window(&window)?;
+        if workspace.is_fullscreen(&window) || workspace.is_maximized(&window) {
+            return None;
+        }
+        let initial_rec = workspace.floating_geometry(&window)?;
+        let edges = edges.unwrap_or_else(|| {
+            let location = start_data.location;
+            let left = location.x < (initial_rec.loc.x + initial_rec.size.w / 2) as f64;
+            let top = location.y < (initial_rec.loc.y + initial_rec.size.h / 2) as f64;
+            match (left, top) {
+                (true, true) => ResizeEdge::TopLeft,
+                (true, false) => ResizeEdge::BottomLeft,
+                (false, true) => ResizeEdge::TopRight,
+                (false, false) => ResizeEdge::BottomRight,
+            }
+        });
+
+        // the window follows the sizes the client commits, see `update_floating_size`
+        workspace.set_resize_edges(&window, edges);
+        let toplevel = window.toplevel();
+        toplevel.with_pending_state(|state| {
+            state.states.set(State::Resizing);
+        });
+        toplevel.send_configure();
+
+        Some(ResizeSurfaceGrab {
+            start_data,
+            window,
+            edges,
+            initial_rec,
+        })
+    }
+
+    fn left(&self) -> bool {
+        moves_left(self.edges)
+    }
+
+    fn right(&self) -> bool {
+        matches!(self.edges, ResizeEdge::Right | ResizeEdge::TopRight | ResizeEdge::BottomRight)
+    }
+
+    fn top(&self) -> bool {
+        moves_top(self.edges)
+    }
+
+    fn bottom(&self) -> bool {
+        matches!(self.edges, ResizeEdge::Bottom | ResizeEdge::BottomLeft | ResizeEdge::BottomRight)
+    }
+}
+
+/// Whether resizing `edges` moves the left edge of a window.
+pub fn moves_left(edges: ResizeEdge) -> bool {
+    matches!(edges, ResizeEdge::Left | ResizeEdge::TopLeft | ResizeEdge::BottomLeft)
+}
+
+/// Whether resizing `edges` moves the top edge of a window.
+pub fn moves_top(edges: ResizeEdge) -> bool {
+    matches!(edges, ResizeEdge::Top | ResizeEdge::TopLeft | ResizeEdge::TopRight)
+}
+
+impl<BackendData: Backend> PointerGrab<MagmaState<BackendData>> for ResizeSurfaceGrab<BackendData> {
+    fn motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // no client has pointer focus while the grab is active
+        handle.motion(data, None, event);
+
+        let delta = (event.location - self.start_data.location).to_i32_round::<i32>();
+        let mut size = self.initial_rec.size;
+        if self.left() {
+            size.w -= delta.x;
+        } else if self.right() {
+            size.w += delta.x;
+        }
+        if self.top() {
+            size.h -= delta.y;
+        } else if self.bottom() {
+            size.h += delta.y;
+        }
+
+        let (min_size, max_size) = with_states(self.window.toplevel().wl_surface(), |states| {
+            let state = states.cached_state.current::<SurfaceCachedState>();
+            (state.min_size, state.max_size)
+        });
+        let max_w = if max_size.w > 0 { max_size.w } else { i32::MAX };
+        let max_h = if max_size.h > 0 { max_size.h } else { i32::MAX };
+        let size = Size::from((
+            size.w.max(min_size.w.max(1)).min(max_w),
+            size.h.max(min_size.h.max(1)).min(max_h),
+        ));
+
+        // the geometry changes once the client commits the size
+        let toplevel = self.window.toplevel();
+        toplevel.with_pending_state(|state| {
+            state.size = Some(size);
+        });
+        toplevel.send_configure();
+    }
+
+    fn relative_motion(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
+        event: &RelativeMotionEvent,
+    ) {
+        handle.relative_motion(data, None, event);
+    }
+
+    fn button(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        event: &ButtonEvent,
+    ) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            handle.unset_grab(data, event.serial, event.time);
+
+            let toplevel = self.window.toplevel();
+            toplevel.with_pending_state(|state| {
+                state.states.unset(State::Resizing);
+            });
+            toplevel.send_configure();
+        }
+    }
+
+    fn axis(
+        &mut self,
+        data: &mut MagmaState<BackendData>,
+        handle: &mut PointerInnerHandle<'_, MagmaState<BackendData>>,
+        details: AxisFrame,
+    ) {
+        handle.axis(data, details)
+    }
+
+    forward_gestures!();
+
+    fn start_data(&self) -> &PointerGrabStartData<MagmaState<BackendData>> {
+        &self.start_data
+    }
+}
@@ -0,0 +1,231 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            element::{
+                memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
+                solid::{SolidColorBuffer, SolidColorRenderElement},
+                Kind,
+            },
+            ImportMem, Renderer,
+        },
+    },
+    desktop::Window,
+    utils::{Logical, Point, Rectangle, Scale, Size, Transform},
+    wayland::{compositor::with_states, shell::xdg::XdgToplevelSurfaceData},
+};
+use tracing::warn;
+
+use super::{
+    binarytree::HorizontalOrVertical,
+    font::{draw_text, text_width, GLYPH_HEIGHT},
+    tiling::split_evenly,
+    workspaces::MagmaWindow,
+};
+
+/// Height of a tab in the tab bar of a group.
+pub const TAB_HEIGHT: i32 = 20;
+/// Height a stacked tab bar leaves to the windows below it, its rows shrink to keep it.
+pub const MIN_WINDOW_HEIGHT: i32 = 100;
+/// Size of a font pixel in tab titles.
+const TITLE_SCALE: i32 = 2;
+/// Space between the left edge of a tab and its title.
+const TITLE_PADDING: i32 = 6;
+const TAB_COLOR: [f32; 4] = [0.15, 0.15, 0.15, 1.0];
+const ACTIVE_TAB_COLOR: [f32; 4] = [0.2, 0.35, 0.55, 1.0];
+/// Premultiplied `[a, r, g, b]`, see [`draw_text`].
+const TITLE_COLOR: [u8; 4] = [0xff, 0xe0, 0xe0, 0xe0];
+
+/// How the tab bar of a group shows its windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKind {
+    /// Tabs side by side in a single row.
+    Tabbed,
+    /// A row per tab.
+    Stacked,
+}
+
+/// Windows sharing a leaf of the layout tree. Only the active one is shown,
+/// below a tab bar with the titles of all of them.
+#[derive(Clone)]
+pub struct Group {
+    pub windows: Vec<Rc<RefCell<MagmaWindow>>>,
+    pub active: usize,
+    pub kind: GroupKind,
+    /// Area of the tab bar, above the windows.
+    pub bar: Rectangle<i32, Logical>,
+    /// Buffers drawing the tabs, updated when rendering.
+    tabs: RefCell<Vec<Tab>>,
+}
+
+#[derive(Clone)]
+struct Tab {
+    background: SolidColorBuffer,
+    /// The title and width `label` was drawn with.
+    title: (String, i32),
+    label: Option<MemoryRenderBuffer>,
+}
+
+impl Group {
+    /// Creates a tabbed group showing `windows[active]`.
+    pub fn new(windows: Vec<Rc<RefCell<MagmaWindow>>>, active: usize) -> Self {
+        Group {
+            windows,
+            active,
+            kind: GroupKind::Tabbed,
+            bar: Rectangle::default(),
+            tabs: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn position(&self, window: &Window) -> Option<usize> {
+        self.windows.iter().position(|w| &w.borrow().window == window)
+    }
+
+    pub fn contains(&self, window: &Window) -> bool {
+        self.position(window).is_some()
+    }
+
+    /// Shows `window`, hiding the other windows of the group.
+    pub fn activate(&mut self, window: &Window) {
+        if let Some(active) = self.position(window) {
+            self.active = active;
+            for (i, magmawindow) in self.windows.iter().enumerate() {
+                magmawindow.borrow_mut().hidden = i != active;
+            }
+        }
+    }
+
+    /// Removes `window`, showing the window next to it if it was the active one.
+    pub fn remove(&mut self, window: &Window) {
+        if let Some(i) = self.position(window) {
+            self.windows.remove(i);
+            if self.active > i || self.active == self.windows.len() {
+                self.active = self.active.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Places the tab bar at the top of `area` and the windows below it.
+    pub fn arrange(&mut self, area: Rectangle<i32, Logical>) {
+        let bar_height = match self.kind {
+            GroupKind::Tabbed => TAB_HEIGHT,
+            GroupKind::Stacked => (TAB_HEIGHT * self.windows.len() as i32)
+                .min(area.size.h - MIN_WINDOW_HEIGHT)
+                .max(TAB_HEIGHT),
+        }
+        .min(area.size.h);
+        self.bar = Rectangle::from_loc_and_size(area.loc, (area.size.w, bar_height));
+        let rec = Rectangle::from_loc_and_size(
+            (area.loc.x, area.loc.y + bar_height),
+            (area.size.w, area.size.h - bar_height),
+        );
+        for (i, magmawindow) in self.windows.iter().enumerate() {
+            let mut magmawindow = magmawindow.borrow_mut();
+            magmawindow.rec = rec;
+            magmawindow.hidden = i != self.active;
+        }
+    }
+
+    /// Areas of the tabs in the tab bar, in the order of the windows.
+    pub fn tab_areas(&self) -> Vec<Rectangle<i32, Logical>> {
+        match self.kind {
+            GroupKind::Tabbed => {
+                split_evenly(self.bar, self.windows.len(), HorizontalOrVertical::Horizontal)
+            }
+            GroupKind::Stacked => {
+                split_evenly(self.bar, self.windows.len(), HorizontalOrVertical::Vertical)
+            }
+        }
+    }
+
+    /// Render elements of the tab bar, drawn `offset` to the top left of its place.
+    pub fn render_elements<R, C>(&self, renderer: &mut R, offset: Point<i32, Logical>) -> Vec<C>
+    where
+        R: Renderer + ImportMem,
+        <R as Renderer>::TextureId: 'static,
+        C: From<SolidColorRenderElement> + From<MemoryRenderBufferRenderElement<R>>,
+    {
+        let mut tabs = self.tabs.borrow_mut();
+        tabs.resize_with(self.windows.len(), || Tab {
+            background: SolidColorBuffer::new((0, 0), TAB_COLOR),
+            title: (String::new(), 0),
+            label: None,
+        });
+
+        let mut elements = Vec::new();
+        for (i, ((magmawindow, area), tab)) in self
+            .windows
+            .iter()
+            .zip(self.tab_areas())
+            .zip(tabs.iter_mut())
+            .enumerate()
+        {
+            let color = if i == self.active { ACTIVE_TAB_COLOR } else { TAB_COLOR };
+            tab.background.update(area.size, color);
+
+            let title = title(&magmawindow.borrow().window);
+            let width = text_width(&title, TITLE_SCALE).min(area.size.w - 2 * TITLE_PADDING);
+            if tab.title != (title.clone(), width) {
+                tab.label = draw_label(&title, width);
+                tab.title = (title, width);
+            }
+
+            let location = area.loc - offset;
+            // titles are in front of their tab, if it is high enough to show them
+            let label_height = GLYPH_HEIGHT * TITLE_SCALE;
+            if let Some(label) = tab.label.as_ref().filter(|_| area.size.h >= label_height) {
+                let label_location =
+                    location + Point::from((TITLE_PADDING, (area.size.h - label_height) / 2));
+                match MemoryRenderBufferRenderElement::from_buffer(
+                    renderer,
+                    label_location.to_physical(1).to_f64(),
+                    label,
+                    None,
+                    None,
+                    None,
+                ) {
+                    Ok(element) => elements.push(C::from(element)),
+                    Err(err) => warn!("Failed to draw tab title: {:?}", err),
+                }
+            }
+            elements.push(C::from(SolidColorRenderElement::from_buffer(
+                &tab.background,
+                location.to_physical(1),
+                Scale::from(1.0),
+                Kind::Unspecified,
+            )));
+        }
+        elements
+    }
+}
+
+fn title(window: &Window) -> String {
+    with_states(window.toplevel().wl_surface(), |states| {
+        states
+            .data_map
+            .get::<XdgToplevelSurfaceData>()
+            .and_then(|data| data.lock().unwrap().title.clone())
+    })
+    .unwrap_or_default()
+}
+
+/// Draws `title` into a buffer `width` pixels wide, cutting it off if it is wider.
+fn draw_label(title: &str, width: i32) -> Option<MemoryRenderBuffer> {
+    if width <= 0 {
+        return None;
+    }
+    let size = Size::from((width, GLYPH_HEIGHT * TITLE_SCALE));
+    let mut pixels = vec![0; (size.w * size.h * 4) as usize];
+    draw_text(&mut pixels, width, title, TITLE_SCALE, TITLE_COLOR);
+    Some(MemoryRenderBuffer::from_memory(
+        &pixels,
+        Fourcc::Argb8888,
+        size,
+        1,
+        Transform::Normal,
+        None,
+    ))
+}
@@ -1,6 +1,10 @@
 pub mod binarytree;
+pub mod font;
+pub mod group;
 pub mod render;
+pub mod swipe;
 pub mod tiling;
 pub mod workspaces;
 pub mod focus;
+pub mod grabs;
 pub mod protocols;
\ No newline at end of file
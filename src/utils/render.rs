@@ -1,14 +1,83 @@
 use smithay::{
     backend::renderer::{
-        element::{surface::WaylandSurfaceRenderElement, texture::TextureRenderElement},
-        ImportAll, ImportMem, Renderer,
+        element::{
+            memory::MemoryRenderBufferRenderElement, solid::SolidColorRenderElement,
+            surface::WaylandSurfaceRenderElement, texture::TextureRenderElement, AsRenderElements,
+        },
+        ImportAll, ImportMem, Renderer, Texture,
     },
+    desktop::layer_map_for_output,
+    output::Output,
     render_elements,
+    utils::{Logical, Point, Scale},
+    wayland::shell::wlr_layer::Layer,
 };
 
+use super::workspaces::Workspace;
+
 render_elements! {
     pub CustomRenderElements<R> where
         R: ImportAll + ImportMem;
     Texture=TextureRenderElement<<R as Renderer>::TextureId>,
     Surface=WaylandSurfaceRenderElement<R>,
+    Solid=SolidColorRenderElement,
+    Memory=MemoryRenderBufferRenderElement<R>,
+}
+
+/// Collects the render elements of an output, front to back: Overlay layers,
+/// fullscreen windows, Top layers, previews of preselected splits, the other workspace windows
+/// and tab bars, then Bottom and Background layers.
+///
+/// `workspaces` are drawn moved by their offset, see
+/// [`Workspaces::rendered_on`](super::workspaces::Workspaces::rendered_on).
+pub fn output_render_elements<R, C>(
+    renderer: &mut R,
+    output: &Output,
+    workspaces: &[(&Workspace, Point<i32, Logical>)],
+) -> Vec<C>
+where
+    R: Renderer + ImportAll + ImportMem,
+    <R as Renderer>::TextureId: Texture + 'static,
+    C: From<WaylandSurfaceRenderElement<R>>
+        + From<SolidColorRenderElement>
+        + From<MemoryRenderBufferRenderElement<R>>,
+{
+    let layer_map = layer_map_for_output(output);
+    let layer_elements = |renderer: &mut R, layers: &[Layer]| -> Vec<C> {
+        layer_map
+            .layers()
+            .rev()
+            .filter(|surface| layers.contains(&surface.layer()))
+            .filter_map(|surface| {
+                layer_map
+                    .layer_geometry(surface)
+                    .map(|geo| (geo.loc, surface))
+            })
+            .flat_map(|(loc, surface)| {
+                AsRenderElements::<R>::render_elements::<WaylandSurfaceRenderElement<R>>(
+                    surface,
+                    renderer,
+                    loc.to_physical_precise_round(1),
+                    Scale::from(1.0),
+                )
+                .into_iter()
+                .map(C::from)
+            })
+            .collect()
+    };
+
+    let mut renderelements: Vec<C> = layer_elements(renderer, &[Layer::Overlay]);
+    for (workspace, shift) in workspaces {
+        renderelements.extend(workspace.fullscreen_render_elements(renderer, *shift));
+    }
+    renderelements.extend(layer_elements(renderer, &[Layer::Top]));
+    for (workspace, shift) in workspaces {
+        renderelements.extend(workspace.preselection_render_elements(*shift));
+    }
+    for (workspace, shift) in workspaces {
+        renderelements.extend(workspace.render_elements(renderer, *shift));
+    }
+    renderelements.extend(layer_elements(renderer, &[Layer::Bottom, Layer::Background]));
+
+    renderelements
 }
@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use smithay::output::Output;
+
+/// Time the workspaces take to settle once the fingers are lifted.
+const SETTLE_DURATION: Duration = Duration::from_millis(200);
+
+/// Workspaces of an output moving along with a touchpad swipe.
+///
+/// The active workspace is drawn `offset` logical pixels to the right, `other`
+/// right next to it on the side that became uncovered.
+#[derive(Debug, Clone)]
+pub struct WorkspaceSwipe {
+    pub output: Output,
+    /// The workspace the swipe reveals, or the one it left after a switch.
+    pub other: Option<u8>,
+    offset: f64,
+    /// Start of the animation moving `offset` back to 0.
+    settle_start: Option<Instant>,
+}
+
+impl WorkspaceSwipe {
+    pub fn new(output: Output) -> Self {
+        WorkspaceSwipe {
+            output,
+            other: None,
+            offset: 0.0,
+            settle_start: None,
+        }
+    }
+
+    /// Follows the fingers, which moved `offset` since the swipe began.
+    pub fn update(&mut self, other: Option<u8>, offset: f64) {
+        self.other = other;
+        // there is nothing to reveal past the first and last workspace
+        self.offset = if other.is_some() { offset } else { 0.0 };
+        self.settle_start = None;
+    }
+
+    /// Starts moving back to the active workspace, which is drawn at `offset`
+    /// with `other` next to it.
+    pub fn settle(&mut self, other: Option<u8>, offset: f64) {
+        self.other = other;
+        self.offset = offset;
+        self.settle_start = Some(Instant::now());
+    }
+
+    /// Horizontal offset of the active workspace right now.
+    pub fn offset(&self) -> f64 {
+        match self.settle_start {
+            Some(start) => {
+                let progress = (start.elapsed().as_secs_f64() / SETTLE_DURATION.as_secs_f64()).min(1.0);
+                // ease out cubic
+                self.offset * (1.0 - progress).powi(3)
+            }
+            None => self.offset,
+        }
+    }
+
+    /// The distance the fingers moved the workspaces before they were lifted.
+    pub fn distance(&self) -> f64 {
+        self.offset
+    }
+
+    /// Whether the workspaces still follow the fingers.
+    pub fn is_following(&self) -> bool {
+        self.settle_start.is_none()
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settle_start
+            .map_or(false, |start| start.elapsed() >= SETTLE_DURATION)
+    }
+}
@@ -1,168 +1,470 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use smithay::{
-    desktop::{Window, layer_map_for_output},
-    utils::{Logical, Physical, Point, Rectangle, Size},
+    desktop::Window,
+    utils::{Logical, Point, Rectangle, Size},
 };
 
+use crate::config::{Config, LayoutKind};
+
 use super::{
-    binarytree::{BinaryTree, HorizontalOrVertical},
+    binarytree::{BinaryTree, HorizontalOrVertical, MAX_RATIO, MIN_RATIO},
     workspaces::{MagmaWindow, Workspace},
 };
 
+/// Narrowest column of a [`Scrolling`] layout, as a share of the area width.
+pub const MIN_COLUMN_WIDTH: f32 = 0.1;
+/// Widest column of a [`Scrolling`] layout, as a share of the area width.
+pub const MAX_COLUMN_WIDTH: f32 = 1.0;
+
 pub enum WindowLayoutEvent {
     Added,
     Removed,
 }
 
-pub fn bsp_layout(
+/// Arranges the tiled windows of a workspace.
+pub trait Layout {
+    fn kind(&self) -> LayoutKind;
+
+    /// Gives the tiled `windows`, in the order they were tiled, their place in `area`.
+    ///
+    /// `tree` is the BSP tree of the workspace, kept up to date whatever the layout.
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        tree: &mut BinaryTree,
+        focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    );
+
+    /// Whether the focused tiled window is the only one shown.
+    fn shows_focused_only(&self) -> bool {
+        false
+    }
+
+    /// Whether the layout has to be updated when the focus changes.
+    fn follows_focus(&self) -> bool {
+        false
+    }
+
+    /// Adds `delta` master windows, returning `false` if the layout has none.
+    fn change_master_count(&mut self, _delta: i32) -> bool {
+        false
+    }
+
+    /// Grows the master area by `delta` of the width, returning `false` if the layout has none.
+    fn change_master_factor(&mut self, _delta: f32) -> bool {
+        false
+    }
+
+    /// Widens the column of `window` by `delta` of the width, returning `false` if the layout has none.
+    fn change_column_width(&mut self, _window: &Window, _delta: f32) -> bool {
+        false
+    }
+
+    /// Takes over reloaded master-stack settings, if the layout has a master area.
+    fn set_master(&mut self, _count: usize, _factor: f32) {}
+
+    /// Takes over a reloaded width for columns that weren't resized, if the layout has columns.
+    fn set_column_width(&mut self, _width: f32) {}
+}
+
+/// Creates the layout `kind`, with the master-stack and column settings of `config`.
+pub fn new_layout(kind: LayoutKind, config: &Config) -> Box<dyn Layout> {
+    match kind {
+        LayoutKind::Bsp => Box::new(Bsp),
+        LayoutKind::MasterStack => Box::new(MasterStack {
+            count: config.master_count,
+            factor: config.master_factor,
+        }),
+        LayoutKind::Monocle => Box::new(Monocle),
+        LayoutKind::Columns => Box::new(Columns),
+        LayoutKind::Grid => Box::new(Grid),
+        LayoutKind::Scrolling => Box::new(Scrolling::new(config.column_width)),
+    }
+}
+
+/// Recursive splits of the binary tree, see [`generate_layout`].
+pub struct Bsp;
+
+impl Layout for Bsp {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Bsp
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        tree: &mut BinaryTree,
+        _focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        for window in windows {
+            window.borrow_mut().hidden = false;
+        }
+        generate_layout(tree, area, gaps);
+    }
+}
+
+/// The first `count` windows side by side on the left, taking `factor` of the
+/// width, the others stacked on top of each other on the right.
+pub struct MasterStack {
+    pub count: usize,
+    pub factor: f32,
+}
+
+impl Layout for MasterStack {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::MasterStack
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        _tree: &mut BinaryTree,
+        _focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        let (masters, stack) = windows.split_at(self.count.min(windows.len()));
+        let (master_area, stack_area) = match (masters.is_empty(), stack.is_empty()) {
+            (false, false) => split_area(area, HorizontalOrVertical::Horizontal, self.factor),
+            _ => (area, area),
+        };
+        place_evenly(masters, master_area, HorizontalOrVertical::Vertical, gaps);
+        place_evenly(stack, stack_area, HorizontalOrVertical::Vertical, gaps);
+    }
+
+    fn change_master_count(&mut self, delta: i32) -> bool {
+        self.count = (self.count as i32 + delta).max(0) as usize;
+        true
+    }
+
+    fn change_master_factor(&mut self, delta: f32) -> bool {
+        self.factor = (self.factor + delta).clamp(MIN_RATIO, MAX_RATIO);
+        true
+    }
+
+    fn set_master(&mut self, count: usize, factor: f32) {
+        self.count = count;
+        self.factor = factor;
+    }
+}
+
+/// One window at a time, taking the whole area.
+pub struct Monocle;
+
+impl Layout for Monocle {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Monocle
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        _tree: &mut BinaryTree,
+        focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        // keep showing the window that was shown if a floating window has focus
+        let shown = windows
+            .iter()
+            .position(|w| Some(&w.borrow().window) == focus)
+            .or_else(|| windows.iter().position(|w| !w.borrow().hidden))
+            .unwrap_or(windows.len().saturating_sub(1));
+        for (i, window) in windows.iter().enumerate() {
+            place(window, area, gaps);
+            window.borrow_mut().hidden = i != shown;
+        }
+    }
+
+    fn shows_focused_only(&self) -> bool {
+        true
+    }
+}
+
+/// Columns of equal width.
+pub struct Columns;
+
+impl Layout for Columns {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Columns
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        _tree: &mut BinaryTree,
+        _focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        place_evenly(windows, area, HorizontalOrVertical::Horizontal, gaps);
+    }
+}
+
+/// Rows of windows as close to a square grid as possible, the last row
+/// sharing its width between the windows left.
+pub struct Grid;
+
+impl Layout for Grid {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Grid
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        _tree: &mut BinaryTree,
+        _focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        if windows.is_empty() {
+            return;
+        }
+        let columns = (windows.len() as f64).sqrt().ceil() as usize;
+        let rows: Vec<_> = windows.chunks(columns).collect();
+        for (row, row_area) in rows
+            .iter()
+            .zip(split_evenly(area, rows.len(), HorizontalOrVertical::Vertical))
+        {
+            place_evenly(row, row_area, HorizontalOrVertical::Horizontal, gaps);
+        }
+    }
+}
+
+/// Columns on a strip that extends past the right edge of the area, scrolled
+/// to keep the focused column in view.
+pub struct Scrolling {
+    /// Width of new columns, as a share of the area width.
+    column_width: f32,
+    /// Widths of the columns that were resized.
+    widths: HashMap<Window, f32>,
+    /// Position of the strip at the left edge of the area.
+    view: i32,
+}
+
+impl Scrolling {
+    pub fn new(column_width: f32) -> Self {
+        Scrolling {
+            column_width,
+            widths: HashMap::new(),
+            view: 0,
+        }
+    }
+
+    fn width(&self, window: &Window) -> f32 {
+        self.widths.get(window).copied().unwrap_or(self.column_width)
+    }
+}
+
+impl Layout for Scrolling {
+    fn kind(&self) -> LayoutKind {
+        LayoutKind::Scrolling
+    }
+
+    fn arrange(
+        &mut self,
+        windows: &[Rc<RefCell<MagmaWindow>>],
+        _tree: &mut BinaryTree,
+        focus: Option<&Window>,
+        area: Rectangle<i32, Logical>,
+        gaps: (i32, i32),
+    ) {
+        self.widths
+            .retain(|window, _| windows.iter().any(|w| &w.borrow().window == window));
+
+        let mut columns = Vec::with_capacity(windows.len());
+        let mut strip = 0;
+        for window in windows {
+            let width = (area.size.w as f32 * self.width(&window.borrow().window)) as i32;
+            columns.push((strip, width));
+            strip += width;
+        }
+
+        // scroll just enough to show all of the focused column
+        if let Some(&(x, width)) = windows
+            .iter()
+            .position(|w| Some(&w.borrow().window) == focus)
+            .and_then(|i| columns.get(i))
+        {
+            self.view = self.view.min(x).max(x + width - area.size.w);
+        }
+        self.view = self.view.min(strip - area.size.w).max(0);
+
+        for (window, (x, width)) in windows.iter().zip(columns) {
+            let column = Rectangle {
+                loc: Point::from((area.loc.x + x - self.view, area.loc.y)),
+                size: Size::from((width, area.size.h)),
+            };
+            place(window, column, gaps);
+        }
+    }
+
+    fn follows_focus(&self) -> bool {
+        true
+    }
+
+    fn change_column_width(&mut self, window: &Window, delta: f32) -> bool {
+        let width = (self.width(window) + delta).clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH);
+        self.widths.insert(window.clone(), width);
+        true
+    }
+
+    fn set_column_width(&mut self, width: f32) {
+        self.column_width = width;
+    }
+}
+
+/// Gives `window` all of `area`, shrunk by the inner gap.
+fn place(window: &Rc<RefCell<MagmaWindow>>, area: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+    let mut window = window.borrow_mut();
+    window.rec = Rectangle {
+        size: Size::from((area.size.w - (gaps.1 * 2), area.size.h - (gaps.1 * 2))),
+        loc: Point::from((area.loc.x + gaps.1, area.loc.y + gaps.1)),
+    };
+    window.hidden = false;
+}
+
+/// Places `windows` next to each other in equal parts of `area`.
+fn place_evenly(
+    windows: &[Rc<RefCell<MagmaWindow>>],
+    area: Rectangle<i32, Logical>,
+    split: HorizontalOrVertical,
+    gaps: (i32, i32),
+) {
+    for (window, part) in windows.iter().zip(split_evenly(area, windows.len(), split)) {
+        place(window, part, gaps);
+    }
+}
+
+/// Splits `area` into `count` equal parts, the last one taking up the rounding remainder.
+pub fn split_evenly(
+    area: Rectangle<i32, Logical>,
+    count: usize,
+    split: HorizontalOrVertical,
+) -> Vec<Rectangle<i32, Logical>> {
+    let mut parts = Vec::with_capacity(count);
+    let mut rest = area;
+    for left in (1..=count).rev() {
+        let (part, remainder) = split_area(rest, split, 1.0 / left as f32);
+        parts.push(part);
+        rest = remainder;
+    }
+    parts
+}
+
+/// Adds or removes `window` from the tiled windows of `workspace`.
+pub fn layout_window(
     workspace: &mut Workspace,
     window: Window,
     event: WindowLayoutEvent,
     gaps: (i32, i32),
 ) {
-    let output = layer_map_for_output(workspace
-        .outputs()
-        .next()
-        .unwrap()).non_exclusive_zone();
-
     match event {
         WindowLayoutEvent::Added => {
-            let window = Rc::new(RefCell::new(MagmaWindow {
+            let window = Rc::new(RefCell::new(MagmaWindow::new(
                 window,
-                rec: Rectangle {
-                    loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
-                    size: Size::from((output.size.w - (gaps.0 * 2), output.size.h - (gaps.0 * 2))),
-                },
-            }));
+                layout_area(workspace, gaps).unwrap_or_default(),
+            )));
             workspace.add_window(window);
 
-            bsp_update_layout(workspace, gaps);
+            update_layout(workspace, gaps);
         }
         WindowLayoutEvent::Removed => {
             workspace.remove_window(&window);
-            bsp_update_layout(workspace, gaps);
+            update_layout(workspace, gaps);
         }
     }
-    dbg!(workspace.layout_tree.clone());
 }
 
-pub fn bsp_update_layout(workspace: &mut Workspace, gaps: (i32, i32)) {
+pub fn update_layout(workspace: &mut Workspace, gaps: (i32, i32)) {
     //recalculate the size and location of the windows
 
-    let output = layer_map_for_output(workspace
-        .outputs()
-        .next()
-        .unwrap()).non_exclusive_zone();
+    let Some(area) = layout_area(workspace, gaps) else {
+        return;
+    };
+    workspace.arrange_tiled(area, gaps);
+    workspace.arrange_windows();
 
-    let output_full = workspace.outputs().next().unwrap().current_mode().unwrap().size;
+    for magmawindow in workspace.arranged_magmawindows() {
+        let xdg_toplevel = magmawindow.window.toplevel();
+        xdg_toplevel.with_pending_state(|state| {
+            state.size = Some(magmawindow.rec.size);
+        });
+        xdg_toplevel.send_configure();
+    }
+}
 
-    match &mut workspace.layout_tree {
+/// The area available to the layout tree of a workspace, inside the outer gaps.
+pub fn layout_area(workspace: &Workspace, gaps: (i32, i32)) -> Option<Rectangle<i32, Logical>> {
+    let output = workspace.usable_area()?;
+
+    Some(Rectangle {
+        loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
+        size: Size::from((output.size.w - (gaps.0 * 2), output.size.h - (gaps.0 * 2))),
+    })
+}
+
+/// Recursively splits `area` between the children of `tree`,
+/// shrinking the area of every window by the inner gap.
+pub fn generate_layout(tree: &mut BinaryTree, area: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+    match tree {
         BinaryTree::Empty => {}
         BinaryTree::Window(w) => {
             w.borrow_mut().rec = Rectangle {
-                loc: Point::from((gaps.0 + gaps.1 + output.loc.x, gaps.0 + gaps.1 + output.loc.y)),
-                size: Size::from((
-                    output.size.w - ((gaps.0 + gaps.1) * 2),
-                    output.size.h - ((gaps.0 + gaps.1) * 2),
-                )),
+                size: Size::from((area.size.w - (gaps.1 * 2), area.size.h - (gaps.1 * 2))),
+                loc: Point::from((area.loc.x + gaps.1, area.loc.y + gaps.1)),
             };
         }
+        BinaryTree::Group(group) => group.arrange(Rectangle {
+            size: Size::from((area.size.w - (gaps.1 * 2), area.size.h - (gaps.1 * 2))),
+            loc: Point::from((area.loc.x + gaps.1, area.loc.y + gaps.1)),
+        }),
         BinaryTree::Split {
-            left,
-            right,
             split,
             ratio,
+            left,
+            right,
         } => {
-            if let BinaryTree::Window(w) = left.as_mut() {
-                generate_layout(
-                    right.as_mut(),
-                    &w,
-                    Rectangle {
-                        loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
-                        size: Size::from((output.size.w - (gaps.0 * 2), output.size.h - (gaps.0 * 2))),
-                    },
-                    *split,
-                    *ratio,
-                    Size::from((output_full.w - gaps.0, output_full.h - gaps.0)),
-                    gaps,
-                )
-            }
+            let (left_area, right_area) = split_area(area, *split, *ratio);
+            generate_layout(left, left_area, gaps);
+            generate_layout(right, right_area, gaps);
         }
     }
-    for magmawindow in workspace.magmawindows() {
-        let xdg_toplevel = magmawindow.window.toplevel();
-        xdg_toplevel.with_pending_state(|state| {
-            state.size = Some(magmawindow.rec.size);
-        });
-        xdg_toplevel.send_configure();
-    }
 }
 
-pub fn generate_layout(
-    tree: &mut BinaryTree,
-    lastwin: &Rc<RefCell<MagmaWindow>>,
-    lastgeo: Rectangle<i32, Logical>,
+/// Splits `area` at `ratio`, side by side for horizontal splits
+/// and on top of each other for vertical ones.
+pub fn split_area(
+    area: Rectangle<i32, Logical>,
     split: HorizontalOrVertical,
     ratio: f32,
-    output: Size<i32, Physical>,
-    gaps: (i32, i32),
-) {
-    let size;
+) -> (Rectangle<i32, Logical>, Rectangle<i32, Logical>) {
     match split {
         HorizontalOrVertical::Horizontal => {
-            size = Size::from(((lastgeo.size.w as f32 * ratio) as i32, lastgeo.size.h));
+            let w = (area.size.w as f32 * ratio) as i32;
+            (
+                Rectangle::from_loc_and_size(area.loc, (w, area.size.h)),
+                Rectangle::from_loc_and_size(
+                    (area.loc.x + w, area.loc.y),
+                    (area.size.w - w, area.size.h),
+                ),
+            )
         }
         HorizontalOrVertical::Vertical => {
-            size = Size::from((lastgeo.size.w, (lastgeo.size.h as f32 * ratio) as i32));
-        }
-    }
-
-    let loc: Point<i32, Logical>;
-    match split {
-        HorizontalOrVertical::Horizontal => {
-            loc = Point::from((lastgeo.loc.x, output.h - size.h));
-        }
-        HorizontalOrVertical::Vertical => {
-            loc = Point::from((output.w - size.w, lastgeo.loc.y));
-        }
-    }
-
-    let recgapped = Rectangle {
-        size: Size::from((size.w - (gaps.1 * 2), (size.h - (gaps.1 * 2)))),
-        loc: Point::from((loc.x + gaps.1, loc.y + gaps.1)),
-    };
-
-    lastwin.borrow_mut().rec = recgapped;
-
-    let loc;
-    match split {
-        HorizontalOrVertical::Horizontal => {
-            loc = Point::from((output.w - size.w, lastgeo.loc.y));
-        }
-        HorizontalOrVertical::Vertical => {
-            loc = Point::from((lastgeo.loc.x, output.h - size.h));
-        }
-    }
-
-    let rec = Rectangle { size, loc };
-    let recgapped = Rectangle {
-        size: Size::from((size.w - (gaps.1 * 2), (size.h - (gaps.1 * 2)))),
-        loc: Point::from((loc.x + gaps.1, loc.y + gaps.1)),
-    };
-    match tree {
-        BinaryTree::Empty => {}
-        BinaryTree::Window(w) => w.borrow_mut().rec = recgapped,
-        BinaryTree::Split {
-            split,
-            ratio,
-            left,
-            right,
-        } => {
-            if let BinaryTree::Window(w) = left.as_mut() {
-                w.borrow_mut().rec = rec;
-                generate_layout(right.as_mut(), &w, rec, *split, *ratio, output, gaps)
-            }
+            let h = (area.size.h as f32 * ratio) as i32;
+            (
+                Rectangle::from_loc_and_size(area.loc, (area.size.w, h)),
+                Rectangle::from_loc_and_size(
+                    (area.loc.x, area.loc.y + h),
+                    (area.size.w, area.size.h - h),
+                ),
+            )
         }
     }
 }
@@ -5,24 +5,68 @@ use std::{
 
 use smithay::{
     backend::renderer::{
-        element::{surface::WaylandSurfaceRenderElement, AsRenderElements},
-        ImportAll, Renderer, Texture,
+        element::{
+            memory::MemoryRenderBufferRenderElement,
+            solid::{SolidColorBuffer, SolidColorRenderElement},
+            surface::WaylandSurfaceRenderElement,
+            AsRenderElements, Kind,
+        },
+        ImportAll, ImportMem, Renderer, Texture,
     },
-    desktop::{space::SpaceElement, Window},
+    desktop::{layer_map_for_output, space::SpaceElement, Window},
     output::Output,
-    utils::{Logical, Point, Rectangle, Scale, Transform},
+    reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+    utils::{Logical, Point, Rectangle, Scale, Size, Transform},
 };
 
-use crate::ipc::MagmaIpcManager;
+use crate::{
+    config::{Config, Direction, LayoutKind, OutputConfig},
+    ipc::MagmaIpcManager,
+};
+
+use super::{
+    binarytree::{BinaryTree, HorizontalOrVertical},
+    group::GroupKind,
+    grabs::{moves_left, moves_top},
+    swipe::WorkspaceSwipe,
+    tiling::{layout_area, new_layout, split_area, update_layout, Layout},
+};
 
-use super::{binarytree::BinaryTree, tiling::bsp_update_layout};
+/// Color of the preview of a preselected split, premultiplied.
+const PRESELECTION_COLOR: [f32; 4] = [0.1, 0.2, 0.35, 0.4];
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct MagmaWindow {
     pub window: Window,
     pub rec: Rectangle<i32, Logical>,
+    /// Geometry the window had when it was last floating.
+    pub floating_rec: Option<Rectangle<i32, Logical>>,
+    pub fullscreen: bool,
+    pub maximized: bool,
+    /// Tiled windows the layout doesn't show, like all but one in monocle.
+    pub hidden: bool,
+    /// Edges dragged in an interactive resize, the opposite edges stay in
+    /// place as the client commits new sizes.
+    pub resize_edges: Option<xdg_toplevel::ResizeEdge>,
 }
 impl MagmaWindow {
+    pub fn new(window: Window, rec: Rectangle<i32, Logical>) -> Self {
+        MagmaWindow {
+            window,
+            rec,
+            floating_rec: None,
+            fullscreen: false,
+            maximized: false,
+            hidden: false,
+            resize_edges: None,
+        }
+    }
+
+    /// Whether the geometry is imposed by the fullscreen or maximized state.
+    pub fn is_arranged(&self) -> bool {
+        self.fullscreen || self.maximized
+    }
+
     fn bbox(&self) -> Rectangle<i32, Logical> {
         let mut bbox = self.window.bbox();
         bbox.loc += self.rec.loc - self.window.geometry().loc;
@@ -33,100 +77,363 @@ impl MagmaWindow {
         self.rec.loc - self.window.geometry().loc
     }
 }
+
+/// A split of a tiled window the next tiled window goes into, see [`Workspace::preselect`].
+#[derive(Debug, Clone)]
+pub struct Preselection {
+    pub window: Window,
+    pub direction: Direction,
+    /// Share of the window's area the next window gets.
+    pub ratio: f32,
+    preview: SolidColorBuffer,
+}
+
+impl Preselection {
+    /// Whether the next window goes before the preselected one in the split.
+    fn first(&self) -> bool {
+        matches!(self.direction, Direction::Left | Direction::Up)
+    }
+
+    /// Ratio of the split in the layout tree.
+    fn split_ratio(&self) -> f32 {
+        if self.first() {
+            self.ratio
+        } else {
+            1.0 - self.ratio
+        }
+    }
+}
+
 pub struct Workspace {
     windows: Vec<Rc<RefCell<MagmaWindow>>>,
-    outputs: Vec<Output>,
+    /// Floating windows, the top-most one last.
+    floating: Vec<Rc<RefCell<MagmaWindow>>>,
+    /// The output the workspace is laid out on, whether it is shown or not.
+    output: Option<Output>,
+    /// Name of the output the workspace is bound to by the configuration.
+    bound_output: Option<String>,
+    focus: Option<Window>,
     pub layout_tree: BinaryTree,
+    layout: Box<dyn Layout>,
+    preselection: Option<Preselection>,
 }
 
 impl Workspace {
-    pub fn new() -> Self {
+    pub fn new(layout: Box<dyn Layout>) -> Self {
         Workspace {
             windows: Vec::new(),
-            outputs: Vec::new(),
+            floating: Vec::new(),
+            output: None,
+            bound_output: None,
+            focus: None,
             layout_tree: BinaryTree::new(),
+            layout,
+            preselection: None,
+        }
+    }
+
+    pub fn layout(&self) -> &dyn Layout {
+        self.layout.as_ref()
+    }
+
+    pub fn layout_mut(&mut self) -> &mut dyn Layout {
+        self.layout.as_mut()
+    }
+
+    /// Switches to another layout. The layout has to be updated afterwards.
+    pub fn set_layout(&mut self, layout: Box<dyn Layout>) {
+        if layout.kind() != LayoutKind::Bsp {
+            self.preselection = None;
+        }
+        self.layout = layout;
+    }
+
+    /// Places the tiled windows in `area` according to the layout.
+    pub fn arrange_tiled(&mut self, area: Rectangle<i32, Logical>, gaps: (i32, i32)) {
+        self.layout
+            .arrange(&self.windows, &mut self.layout_tree, self.focus.as_ref(), area, gaps);
+        self.update_preselection_preview();
+    }
+
+    /// Splits the focused tiled window in `direction` for the next tiled window,
+    /// which gets `ratio` of its area. Only the BSP layout has splits to preselect.
+    ///
+    /// Returns `false` if there is no focused tiled window.
+    pub fn preselect(&mut self, direction: Direction, ratio: f32) -> bool {
+        let Some(window) = self.focus.clone() else {
+            return false;
+        };
+        if self.layout.kind() != LayoutKind::Bsp || !self.layout_tree.contains(&window) {
+            return false;
+        }
+        self.preselection = Some(Preselection {
+            window,
+            direction,
+            ratio,
+            preview: SolidColorBuffer::new((0, 0), PRESELECTION_COLOR),
+        });
+        self.update_preselection_preview();
+        true
+    }
+
+    /// Resizes the preview to the area of the preselected split.
+    fn update_preselection_preview(&mut self) {
+        let Some(area) = self.preselection_area() else {
+            return;
+        };
+        if let Some(preselection) = self.preselection.as_mut() {
+            preselection.preview.update(area.size, PRESELECTION_COLOR);
+        }
+    }
+
+    pub fn cancel_preselect(&mut self) {
+        self.preselection = None;
+    }
+
+    pub fn preselection(&self) -> Option<&Preselection> {
+        self.preselection.as_ref()
+    }
+
+    /// Where the next tiled window goes, if a split is preselected.
+    pub fn preselection_area(&self) -> Option<Rectangle<i32, Logical>> {
+        let preselection = self.preselection.as_ref()?;
+        let rec = self.find_window(&preselection.window)?.borrow().rec;
+        let (first, second) = split_area(
+            rec,
+            preselection.direction.into(),
+            preselection.split_ratio(),
+        );
+        Some(if preselection.first() { first } else { second })
+    }
+
+    /// The window that last had keyboard focus on this workspace.
+    pub fn focused(&self) -> Option<&Window> {
+        self.focus.as_ref()
+    }
+
+    pub fn set_focused(&mut self, window: Window) {
+        if !self.contains_window(&window) {
+            return;
+        }
+        if self.layout.shows_focused_only() && !self.is_floating(&window) {
+            for magmawindow in &self.windows {
+                let mut magmawindow = magmawindow.borrow_mut();
+                magmawindow.hidden = magmawindow.window != window;
+            }
+        }
+        if self.layout.kind() == LayoutKind::Bsp {
+            if let Some(group) = self.layout_tree.group_mut(&window) {
+                group.activate(&window);
+            }
         }
+        self.focus = Some(window);
     }
 
+    /// All windows, tiled ones first.
     pub fn windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
         self.windows
             .iter()
+            .chain(self.floating.iter())
             .map(|w| Ref::map(w.borrow(), |hw| &hw.window))
     }
 
+    /// All windows, tiled ones first.
     pub fn magmawindows(&self) -> impl Iterator<Item = Ref<'_, MagmaWindow>> {
+        self.windows
+            .iter()
+            .chain(self.floating.iter())
+            .map(|w| Ref::map(w.borrow(), |hw| hw))
+    }
+
+    pub fn tiled_magmawindows(&self) -> impl Iterator<Item = Ref<'_, MagmaWindow>> {
         self.windows.iter().map(|w| Ref::map(w.borrow(), |hw| hw))
     }
 
+    /// Windows whose size is chosen by the compositor: tiled, fullscreen and maximized ones.
+    pub fn arranged_magmawindows(&self) -> impl Iterator<Item = Ref<'_, MagmaWindow>> {
+        self.windows
+            .iter()
+            .chain(self.floating.iter().filter(|w| w.borrow().is_arranged()))
+            .map(|w| Ref::map(w.borrow(), |hw| hw))
+    }
+
+    /// Windows from top to bottom: fullscreen, floating, maximized and then tiled ones.
+    fn stacked(&self) -> Vec<&Rc<RefCell<MagmaWindow>>> {
+        let mut stacked: Vec<_> = self
+            .windows
+            .iter()
+            .chain(self.floating.iter())
+            .filter(|w| w.borrow().fullscreen)
+            .collect();
+        stacked.extend(self.floating.iter().rev().filter(|w| !w.borrow().fullscreen));
+        stacked.extend(self.windows.iter().filter(|w| {
+            let w = w.borrow();
+            w.maximized && !w.fullscreen
+        }));
+        stacked.extend(self.windows.iter().filter(|w| {
+            let w = w.borrow();
+            !w.is_arranged() && !w.hidden
+        }));
+        stacked
+    }
+
+    pub fn is_floating(&self, window: &Window) -> bool {
+        self.floating.iter().any(|w| &w.borrow().window == window)
+    }
+
     pub fn add_window(&mut self, window: Rc<RefCell<MagmaWindow>>) {
         // add window to vec and remap if exists
         self.windows
             .retain(|w| &w.borrow().window != &window.borrow().window);
         self.windows.push(window.clone());
-        self.layout_tree
-            .insert(window, self.layout_tree.next_split(), 0.5);
+        match self.preselection.take() {
+            Some(preselection) if self.layout_tree.contains(&preselection.window) => {
+                self.layout_tree.insert_next_to(
+                    &preselection.window,
+                    window,
+                    preselection.direction.into(),
+                    preselection.split_ratio(),
+                    preselection.first(),
+                );
+            }
+            _ => self
+                .layout_tree
+                .insert(window, self.layout_tree.next_split(), 0.5),
+        }
+    }
+
+    /// Adds a window on top of the floating stack, keeping its geometry.
+    pub fn add_floating_window(&mut self, window: Rc<RefCell<MagmaWindow>>) {
+        self.floating
+            .retain(|w| &w.borrow().window != &window.borrow().window);
+        self.floating.push(window);
     }
 
     pub fn remove_window(&mut self, window: &Window) -> Option<Rc<RefCell<MagmaWindow>>> {
         let mut removed = None;
-        self.windows.retain(|w| {
+        let mut retain = |w: &Rc<RefCell<MagmaWindow>>| {
             if &w.borrow().window == window {
                 removed = Some(w.clone());
                 false
             } else {
                 true
             }
-        });
+        };
+        self.windows.retain(&mut retain);
+        self.floating.retain(&mut retain);
         self.layout_tree.remove(&window);
+        if self.focus.as_ref() == Some(window) {
+            self.focus = None;
+        }
+        if self.preselection.as_ref().map(|p| &p.window) == Some(window) {
+            self.preselection = None;
+        }
         removed
     }
 
-    pub fn render_elements<'a, R: Renderer + ImportAll, C: From<WaylandSurfaceRenderElement<R>>>(
+    /// Render elements of the windows that aren't fullscreen and of the tab bars
+    /// of groups, moved by `shift` from their place on the output.
+    pub fn render_elements<'a, R, C>(&self, renderer: &mut R, shift: Point<i32, Logical>) -> Vec<C>
+    where
+        R: Renderer + ImportAll + ImportMem,
+        <R as Renderer>::TextureId: Texture + 'static,
+        C: From<WaylandSurfaceRenderElement<R>>
+            + From<SolidColorRenderElement>
+            + From<MemoryRenderBufferRenderElement<R>>,
+    {
+        let mut render_elements: Vec<C> = Vec::new();
+        let offset = self.output_location() - shift;
+        // fullscreen windows are rendered separately, above the Top layers
+        for element in self.stacked().into_iter().filter(|w| !w.borrow().fullscreen) {
+            render_elements.append(&mut element.borrow().window.render_elements(
+                renderer,
+                (element.borrow().render_location() - offset).to_physical(1),
+                Scale::from(1.0),
+            ));
+        }
+        // only the BSP layout places groups, tab bars are below the windows
+        if self.layout.kind() == LayoutKind::Bsp {
+            for group in self.layout_tree.groups() {
+                render_elements.extend(group.render_elements(renderer, offset));
+            }
+        }
+        render_elements
+    }
+
+    /// Render element previewing the preselected split, moved by `shift` like the windows.
+    pub fn preselection_render_elements<C: From<SolidColorRenderElement>>(
+        &self,
+        shift: Point<i32, Logical>,
+    ) -> Vec<C> {
+        let (Some(preselection), Some(area)) = (self.preselection.as_ref(), self.preselection_area()) else {
+            return Vec::new();
+        };
+        let location = area.loc - (self.output_location() - shift);
+        vec![C::from(SolidColorRenderElement::from_buffer(
+            &preselection.preview,
+            location.to_physical(1),
+            Scale::from(1.0),
+            Kind::Unspecified,
+        ))]
+    }
+
+    pub fn fullscreen_render_elements<R: Renderer + ImportAll, C: From<WaylandSurfaceRenderElement<R>>>(
         &self,
         renderer: &mut R,
+        shift: Point<i32, Logical>,
     ) -> Vec<C>
     where
         <R as Renderer>::TextureId: Texture + 'static,
     {
         let mut render_elements: Vec<C> = Vec::new();
-        for element in &self.windows {
+        let offset = self.output_location() - shift;
+        for element in self.stacked().into_iter().filter(|w| w.borrow().fullscreen) {
             render_elements.append(&mut element.borrow().window.render_elements(
                 renderer,
-                element.borrow().render_location().to_physical(1),
+                (element.borrow().render_location() - offset).to_physical(1),
                 Scale::from(1.0),
             ));
         }
         render_elements
     }
 
-    pub fn outputs(&self) -> impl Iterator<Item = &Output> {
-        self.outputs.iter()
+    pub fn output(&self) -> Option<&Output> {
+        self.output.as_ref()
     }
 
-    pub fn add_output(&mut self, output: Output) {
-        self.outputs.push(output);
+    /// Moves the workspace to another output. The layout has to be updated afterwards,
+    /// floating windows keep their position relative to the output.
+    pub fn set_output(&mut self, output: Option<Output>) {
+        let offset = output.as_ref().map(|o| o.current_location()).unwrap_or_default()
+            - self.output_location();
+        for magmawindow in &self.floating {
+            let mut magmawindow = magmawindow.borrow_mut();
+            magmawindow.rec.loc += offset;
+            if let Some(rec) = magmawindow.floating_rec.as_mut() {
+                rec.loc += offset;
+            }
+        }
+        self.output = output;
     }
 
-    pub fn remove_outputs(&mut self) {
-        self.outputs.clear()
+    fn output_location(&self) -> Point<i32, Logical> {
+        self.output
+            .as_ref()
+            .map(|o| o.current_location())
+            .unwrap_or_default()
     }
 
-    pub fn output_geometry(&self, o: &Output) -> Option<Rectangle<i32, Logical>> {
-        if !self.outputs.contains(o) {
-            return None;
-        }
+    /// Geometry of the workspace's output in the global coordinate space.
+    pub fn output_geometry(&self) -> Option<Rectangle<i32, Logical>> {
+        self.output.as_ref().and_then(output_geometry)
+    }
 
-        let transform: Transform = o.current_transform();
-        o.current_mode().map(|mode| {
-            Rectangle::from_loc_and_size(
-                (0, 0),
-                transform
-                    .transform_size(mode.size)
-                    .to_f64()
-                    .to_logical(o.current_scale().fractional_scale())
-                    .to_i32_ceil(),
-            )
-        })
+    /// Usable area of the output in the global coordinate space, excluding exclusive layers.
+    pub fn usable_area(&self) -> Option<Rectangle<i32, Logical>> {
+        let output = self.output.as_ref()?;
+        let mut zone = layer_map_for_output(output).non_exclusive_zone();
+        zone.loc += output.current_location();
+        Some(zone)
     }
 
     pub fn window_under<P: Into<Point<f64, Logical>>>(
@@ -134,8 +441,8 @@ impl Workspace {
         point: P,
     ) -> Option<(Ref<'_, Window>, Point<i32, Logical>)> {
         let point = point.into();
-        self.windows
-            .iter()
+        self.stacked()
+            .into_iter()
             .filter(|e| e.borrow().bbox().to_f64().contains(point))
             .find_map(|e| {
                 // we need to offset the point to the location where the surface is actually drawn
@@ -151,26 +458,501 @@ impl Workspace {
             })
     }
 
+    /// Like [`Workspace::window_under`], only considering fullscreen windows.
+    pub fn fullscreen_window_under<P: Into<Point<f64, Logical>>>(
+        &self,
+        point: P,
+    ) -> Option<(Ref<'_, Window>, Point<i32, Logical>)> {
+        self.window_under(point)
+            .filter(|(window, _)| self.is_fullscreen(window))
+    }
+
+    pub fn is_fullscreen(&self, window: &Window) -> bool {
+        self.find_window(window)
+            .map_or(false, |w| w.borrow().fullscreen)
+    }
+
+    pub fn is_maximized(&self, window: &Window) -> bool {
+        self.find_window(window)
+            .map_or(false, |w| w.borrow().maximized)
+    }
+
+    /// Sets the fullscreen state of a window.
+    ///
+    /// Tiled windows keep their slot in the layout tree, floating ones get their
+    /// geometry back once neither fullscreen nor maximized.
+    /// The layout has to be updated afterwards.
+    pub fn set_fullscreen(&mut self, window: &Window, fullscreen: bool) {
+        self.change_window_state(window, |w| w.fullscreen = fullscreen);
+    }
+
+    /// Sets the maximized state of a window, see [`Workspace::set_fullscreen`].
+    pub fn set_maximized(&mut self, window: &Window, maximized: bool) {
+        self.change_window_state(window, |w| w.maximized = maximized);
+    }
+
+    fn change_window_state(&mut self, window: &Window, change: impl FnOnce(&mut MagmaWindow)) {
+        let floating = self.is_floating(window);
+        let Some(magmawindow) = self.find_window(window) else {
+            return;
+        };
+        let mut magmawindow = magmawindow.borrow_mut();
+        let was_arranged = magmawindow.is_arranged();
+        change(&mut magmawindow);
+
+        let (fullscreen, maximized) = (magmawindow.fullscreen, magmawindow.maximized);
+        magmawindow.window.toplevel().with_pending_state(|state| {
+            if fullscreen {
+                state.states.set(xdg_toplevel::State::Fullscreen);
+            } else {
+                state.states.unset(xdg_toplevel::State::Fullscreen);
+            }
+            if maximized {
+                state.states.set(xdg_toplevel::State::Maximized);
+            } else {
+                state.states.unset(xdg_toplevel::State::Maximized);
+            }
+        });
+
+        if floating && !magmawindow.is_arranged() {
+            if was_arranged {
+                if let Some(rec) = magmawindow.floating_rec {
+                    magmawindow.rec = rec;
+                }
+            }
+            let size = magmawindow.rec.size;
+            magmawindow.window.toplevel().with_pending_state(|state| {
+                state.size = (size.w > 0 && size.h > 0).then_some(size);
+            });
+            // arranged windows are configured with the layout
+            magmawindow.window.toplevel().send_configure();
+        } else if floating && !was_arranged {
+            magmawindow.floating_rec = Some(magmawindow.rec);
+        }
+    }
+
+    /// Gives fullscreen windows the whole output and maximized ones its usable area.
+    pub fn arrange_windows(&mut self) {
+        let (Some(output_geometry), Some(zone)) = (self.output_geometry(), self.usable_area()) else {
+            return;
+        };
+
+        for magmawindow in self.windows.iter().chain(self.floating.iter()) {
+            let mut magmawindow = magmawindow.borrow_mut();
+            if magmawindow.fullscreen {
+                magmawindow.rec = output_geometry;
+            } else if magmawindow.maximized {
+                magmawindow.rec = zone;
+            }
+        }
+    }
+
     pub fn contains_window(&self, window: &Window) -> bool {
-        self.windows.iter().any(|w| &w.borrow().window == window)
+        self.windows
+            .iter()
+            .chain(self.floating.iter())
+            .any(|w| &w.borrow().window == window)
+    }
+
+    /// Finds the window next to the focused one in the given direction.
+    ///
+    /// Candidates have to lie entirely on that side of the focused window,
+    /// the closest one wins, preferring windows that overlap on the other axis.
+    pub fn window_in_direction(&self, direction: Direction) -> Option<Window> {
+        let focused = self.focus.as_ref()?;
+        let from = self
+            .magmawindows()
+            .find(|w| &w.window == focused)
+            .map(|w| w.rec)?;
+
+        closest_in_direction(
+            from,
+            self.magmawindows()
+                .filter(|w| &w.window != focused && !w.hidden)
+                .map(|w| (w.rec, w.window.clone())),
+            direction,
+        )
+    }
+
+    fn find_window(&self, window: &Window) -> Option<Rc<RefCell<MagmaWindow>>> {
+        self.windows
+            .iter()
+            .chain(self.floating.iter())
+            .find(|w| &w.borrow().window == window)
+            .cloned()
+    }
+
+    /// Exchanges the places of two windows in the layout tree.
+    pub fn swap_windows(&mut self, a: &Window, b: &Window) {
+        if !self.layout_tree.contains(a) || !self.layout_tree.contains(b) {
+            return;
+        }
+        if let (Some(a), Some(b)) = (self.find_window(a), self.find_window(b)) {
+            self.layout_tree.swap(&a, &b);
+            let i = self.windows.iter().position(|w| Rc::ptr_eq(w, &a)).unwrap();
+            let j = self.windows.iter().position(|w| Rc::ptr_eq(w, &b)).unwrap();
+            self.windows.swap(i, j);
+        }
+    }
+
+    /// Detaches `window` from the layout tree and splits the leaf of `target` with it,
+    /// placing `window` on the side of `target` facing `direction`.
+    ///
+    /// Layouts other than BSP have no splits to move into, the windows swap places.
+    pub fn move_window(&mut self, window: &Window, target: &Window, direction: Direction) {
+        if self.layout.kind() != LayoutKind::Bsp {
+            self.swap_windows(window, target);
+            return;
+        }
+        if window == target
+            || !self.layout_tree.contains(window)
+            || !self.layout_tree.contains(target)
+        {
+            return;
+        }
+        if let Some(magmawindow) = self.find_window(window) {
+            self.layout_tree.remove(window);
+            let first = matches!(direction, Direction::Left | Direction::Up);
+            self.layout_tree
+                .insert_next_to(target, magmawindow, direction.into(), 0.5, first);
+        }
+    }
+
+    /// Moves `window` into the leaf of `target`, grouping the two as tabs.
+    /// Only the BSP layout has leaves to share.
+    ///
+    /// Returns `false` if nothing was grouped.
+    pub fn group_windows(&mut self, window: &Window, target: &Window) -> bool {
+        if self.layout.kind() != LayoutKind::Bsp
+            || window == target
+            || !self.layout_tree.contains(window)
+            || !self.layout_tree.contains(target)
+            || self.layout_tree.group(target).map_or(false, |g| g.contains(window))
+        {
+            return false;
+        }
+        let Some(magmawindow) = self.find_window(window) else {
+            return false;
+        };
+        self.layout_tree.remove(window);
+        self.layout_tree.add_to_group(target, magmawindow)
+    }
+
+    /// Takes `window` out of its group and places it to the right of the rest of the group.
+    ///
+    /// Returns `false` if the window is not part of a group.
+    pub fn ungroup_window(&mut self, window: &Window) -> bool {
+        let Some(other) = self.layout_tree.group(window).and_then(|group| {
+            group
+                .windows
+                .iter()
+                .map(|w| w.borrow().window.clone())
+                .find(|w| w != window)
+        }) else {
+            return false;
+        };
+        let Some(magmawindow) = self.find_window(window) else {
+            return false;
+        };
+        self.layout_tree.remove(window);
+        self.layout_tree.insert_next_to(
+            &other,
+            magmawindow,
+            HorizontalOrVertical::Horizontal,
+            0.5,
+            false,
+        )
+    }
+
+    /// The window of the next or previous tab in the group of the focused window.
+    pub fn cycle_tab(&self, forward: bool) -> Option<Window> {
+        let focused = self.focus.as_ref()?;
+        let group = self.layout_tree.group(focused)?;
+        let len = group.windows.len();
+        let index = group.position(focused)?;
+        let index = if forward { (index + 1) % len } else { (index + len - 1) % len };
+        let window = group.windows[index].borrow().window.clone();
+        Some(window)
+    }
+
+    /// Switches the group of `window` between tabbed and stacked.
+    ///
+    /// Returns `false` if the window is not part of a group.
+    pub fn toggle_group_kind(&mut self, window: &Window) -> bool {
+        let Some(group) = self.layout_tree.group_mut(window) else {
+            return false;
+        };
+        group.kind = match group.kind {
+            GroupKind::Tabbed => GroupKind::Stacked,
+            GroupKind::Stacked => GroupKind::Tabbed,
+        };
+        true
+    }
+
+    /// Resizes a tiled window by moving its boundary on the `direction` side.
+    /// Only the splits of the BSP layout can be moved.
+    pub fn resize_window(&mut self, window: &Window, direction: Direction, amount: f32, gaps: (i32, i32)) {
+        if self.layout.kind() != LayoutKind::Bsp {
+            return;
+        }
+        let Some(area) = layout_area(self, gaps) else {
+            return;
+        };
+        if self.layout_tree.resize(window, direction, amount, area) {
+            update_layout(self, gaps);
+        }
+    }
+
+    /// Returns the window after (or before) the focused one in mapping order, wrapping around.
+    pub fn cycle_window(&self, forward: bool) -> Option<Window> {
+        let windows: Vec<Window> = self.windows().map(|w| w.clone()).collect();
+        let len = windows.len();
+        if len == 0 {
+            return None;
+        }
+        let index = match &self.focus {
+            Some(focused) => windows
+                .iter()
+                .position(|w| w == focused)
+                .map(|i| if forward { (i + 1) % len } else { (i + len - 1) % len })
+                .unwrap_or(0),
+            None => 0,
+        };
+        Some(windows[index].clone())
+    }
+
+    /// Takes a tiled window out of the layout tree and puts it on top of the floating stack.
+    ///
+    /// The window gets back its last floating geometry, or is centered on the output
+    /// at `size` (its current size if `None`) when floating for the first time.
+    pub fn float_window(&mut self, window: &Window, size: Option<Size<i32, Logical>>) {
+        if self.is_floating(window) {
+            return;
+        }
+        if let Some(magmawindow) = self.remove_window(window) {
+            self.place_floating(magmawindow, size);
+        }
+    }
+
+    /// Adds a window that wasn't on the workspace yet as floating, centered at `size`.
+    pub fn map_floating(&mut self, window: Window, size: Size<i32, Logical>) {
+        let magmawindow = Rc::new(RefCell::new(MagmaWindow::new(window, Rectangle::default())));
+        self.place_floating(magmawindow, Some(size));
+    }
+
+    fn place_floating(
+        &mut self,
+        magmawindow: Rc<RefCell<MagmaWindow>>,
+        size: Option<Size<i32, Logical>>,
+    ) {
+        let window = magmawindow.borrow().window.clone();
+        {
+            let mut magmawindow = magmawindow.borrow_mut();
+            magmawindow.hidden = false;
+            let size = size.unwrap_or(magmawindow.rec.size);
+            magmawindow.rec = magmawindow
+                .floating_rec
+                .unwrap_or_else(|| self.centered(size));
+            magmawindow.window.toplevel().with_pending_state(|state| {
+                state.size = (magmawindow.rec.size.w > 0 && magmawindow.rec.size.h > 0)
+                    .then_some(magmawindow.rec.size);
+            });
+            magmawindow.window.toplevel().send_configure();
+        }
+        self.add_floating_window(magmawindow);
+        self.focus = Some(window);
+    }
+
+    /// Re-inserts a floating window into the layout tree, remembering its floating geometry.
+    pub fn tile_window(&mut self, window: &Window) {
+        if !self.is_floating(window) {
+            return;
+        }
+        if let Some(magmawindow) = self.remove_window(window) {
+            {
+                let mut magmawindow = magmawindow.borrow_mut();
+                magmawindow.floating_rec = Some(magmawindow.rec);
+            }
+            self.add_window(magmawindow);
+            self.focus = Some(window.clone());
+        }
+    }
+
+    pub fn floating_geometry(&self, window: &Window) -> Option<Rectangle<i32, Logical>> {
+        self.floating
+            .iter()
+            .find(|w| &w.borrow().window == window)
+            .map(|w| w.borrow().rec)
+    }
+
+    pub fn set_floating_geometry(&mut self, window: &Window, rec: Rectangle<i32, Logical>) {
+        if let Some(w) = self.floating.iter().find(|w| &w.borrow().window == window) {
+            w.borrow_mut().rec = rec;
+        }
+    }
+
+    /// Starts an interactive resize of `edges` of a floating window.
+    pub fn set_resize_edges(&mut self, window: &Window, edges: xdg_toplevel::ResizeEdge) {
+        if let Some(w) = self.floating.iter().find(|w| &w.borrow().window == window) {
+            w.borrow_mut().resize_edges = Some(edges);
+        }
+    }
+
+    /// Moves a floating window to the top of the floating stack.
+    pub fn raise_window(&mut self, window: &Window) {
+        if let Some(index) = self
+            .floating
+            .iter()
+            .position(|w| &w.borrow().window == window)
+        {
+            let magmawindow = self.floating.remove(index);
+            self.floating.push(magmawindow);
+        }
+    }
+
+    /// Lets a floating window follow the size the client committed.
+    /// Windows that are centered on the output stay centered, windows being
+    /// resized keep the edges opposite to the dragged ones in place.
+    pub fn update_floating_size(&mut self, window: &Window) {
+        let Some(magmawindow) = self
+            .floating
+            .iter()
+            .find(|w| &w.borrow().window == window)
+            .cloned()
+        else {
+            return;
+        };
+        let mut magmawindow = magmawindow.borrow_mut();
+        let size = magmawindow.window.geometry().size;
+        let edges = magmawindow.resize_edges;
+        // the last commit of a resize is the first one without the resizing state
+        let states = magmawindow.window.toplevel().current_state().states;
+        if !states.contains(xdg_toplevel::State::Resizing) {
+            magmawindow.resize_edges = None;
+        }
+        if size.w <= 0 || size.h <= 0 || size == magmawindow.rec.size || magmawindow.is_arranged() {
+            return;
+        }
+        if let Some(edges) = edges {
+            let rec = magmawindow.rec;
+            if moves_left(edges) {
+                magmawindow.rec.loc.x = rec.loc.x + rec.size.w - size.w;
+            }
+            if moves_top(edges) {
+                magmawindow.rec.loc.y = rec.loc.y + rec.size.h - size.h;
+            }
+            magmawindow.rec.size = size;
+        } else if magmawindow.rec == self.centered(magmawindow.rec.size) {
+            magmawindow.rec = self.centered(size);
+        } else {
+            magmawindow.rec.size = size;
+        }
+    }
+
+    /// A rectangle of `size` centered on the usable area of the output.
+    fn centered(&self, size: Size<i32, Logical>) -> Rectangle<i32, Logical> {
+        let Some(zone) = self.usable_area() else {
+            return Rectangle::from_loc_and_size((0, 0), size);
+        };
+        Rectangle::from_loc_and_size(
+            (
+                zone.loc.x + (zone.size.w - size.w) / 2,
+                zone.loc.y + (zone.size.h - size.h) / 2,
+            ),
+            size,
+        )
     }
 }
 
+/// Geometry of an output in the global coordinate space.
+pub fn output_geometry(output: &Output) -> Option<Rectangle<i32, Logical>> {
+    let transform: Transform = output.current_transform();
+    output.current_mode().map(|mode| {
+        Rectangle::from_loc_and_size(
+            output.current_location(),
+            transform
+                .transform_size(mode.size)
+                .to_f64()
+                .to_logical(output.current_scale().fractional_scale())
+                .to_i32_ceil(),
+        )
+    })
+}
+
+/// Picks the candidate next to `from` in the given direction.
+///
+/// Candidates have to lie entirely on that side of `from`,
+/// the closest one wins, preferring those that overlap on the other axis.
+fn closest_in_direction<T>(
+    from: Rectangle<i32, Logical>,
+    candidates: impl Iterator<Item = (Rectangle<i32, Logical>, T)>,
+    direction: Direction,
+) -> Option<T> {
+    candidates
+        .filter_map(|(to, candidate)| {
+            let (distance, overlaps, offset) = match direction {
+                Direction::Left => (
+                    from.loc.x - (to.loc.x + to.size.w),
+                    overlap(from.loc.y, from.size.h, to.loc.y, to.size.h),
+                    center(from.loc.y, from.size.h) - center(to.loc.y, to.size.h),
+                ),
+                Direction::Right => (
+                    to.loc.x - (from.loc.x + from.size.w),
+                    overlap(from.loc.y, from.size.h, to.loc.y, to.size.h),
+                    center(from.loc.y, from.size.h) - center(to.loc.y, to.size.h),
+                ),
+                Direction::Up => (
+                    from.loc.y - (to.loc.y + to.size.h),
+                    overlap(from.loc.x, from.size.w, to.loc.x, to.size.w),
+                    center(from.loc.x, from.size.w) - center(to.loc.x, to.size.w),
+                ),
+                Direction::Down => (
+                    to.loc.y - (from.loc.y + from.size.h),
+                    overlap(from.loc.x, from.size.w, to.loc.x, to.size.w),
+                    center(from.loc.x, from.size.w) - center(to.loc.x, to.size.w),
+                ),
+            };
+            if distance < 0 {
+                return None;
+            }
+            Some(((!overlaps, distance, offset.abs()), candidate))
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, candidate)| candidate)
+}
+
+fn overlap(a_start: i32, a_len: i32, b_start: i32, b_len: i32) -> bool {
+    a_start < b_start + b_len && b_start < a_start + a_len
+}
+
+fn center(start: i32, len: i32) -> i32 {
+    start + len / 2
+}
+
 pub struct Workspaces {
     workspaces: Vec<Workspace>,
+    /// Connected outputs in the order they were added, with the workspace each one shows.
+    outputs: Vec<(Output, Option<u8>)>,
+    /// The workspace holding keyboard focus, shown on the focused output.
     pub current: u8,
+    /// Workspaces following a touchpad swipe on one of the outputs.
+    pub swipe: Option<WorkspaceSwipe>,
 }
 
 impl Workspaces {
-    pub fn new(workspaceamount: u8) -> Self {
+    pub fn new(config: &Config) -> Self {
         Workspaces {
-            workspaces: (0..workspaceamount).map(|_| Workspace::new()).collect(),
+            workspaces: (0..config.workspaces)
+                .map(|id| Workspace::new(new_layout(config.workspace_layout(id), config)))
+                .collect(),
+            outputs: Vec::new(),
             current: 0,
+            swipe: None,
         }
     }
 
-    pub fn outputs (&self) -> impl Iterator<Item = &Output> {
-        self.workspaces.iter().flat_map(|w| w.outputs())
+    pub fn outputs(&self) -> impl Iterator<Item = &Output> {
+        self.outputs.iter().map(|(output, _)| output)
     }
 
     pub fn iter(&mut self) -> impl Iterator<Item = &mut Workspace> {
@@ -185,6 +967,249 @@ impl Workspaces {
         &self.workspaces[self.current as usize]
     }
 
+    /// The output showing the current workspace.
+    pub fn current_output(&self) -> Option<&Output> {
+        self.outputs
+            .iter()
+            .find(|(_, active)| *active == Some(self.current))
+            .map(|(output, _)| output)
+    }
+
+    /// The workspace shown on `output`.
+    pub fn active_on(&self, output: &Output) -> Option<&Workspace> {
+        self.active_id_on(output)
+            .map(|id| &self.workspaces[id as usize])
+    }
+
+    pub fn active_id_on(&self, output: &Output) -> Option<u8> {
+        self.outputs
+            .iter()
+            .find(|(o, _)| o == output)
+            .and_then(|(_, active)| *active)
+    }
+
+    /// The closest workspace after (or before) the one shown on `output` that
+    /// isn't shown on or bound to another output.
+    pub fn neighbour(&self, output: &Output, forward: bool) -> Option<u8> {
+        let active = self.active_id_on(output)?;
+        let mut ids: Box<dyn Iterator<Item = u8>> = if forward {
+            Box::new(active + 1..self.workspaces.len() as u8)
+        } else {
+            Box::new((0..active).rev())
+        };
+        ids.find(|id| {
+            let bound = self.workspaces[*id as usize].bound_output.as_ref();
+            !self.is_visible(*id) && bound.map_or(true, |name| *name == output.name())
+        })
+    }
+
+    /// Lays the hidden workspace `id` out on `output`, so it can be drawn there.
+    pub fn arrange_on(&mut self, id: u8, output: &Output, gaps: (i32, i32)) {
+        let workspace = &mut self.workspaces[id as usize];
+        if workspace.output() != Some(output) {
+            workspace.set_output(Some(output.clone()));
+            update_layout(workspace, gaps);
+        }
+    }
+
+    /// The workspaces to draw on `output` with the offset to draw them at,
+    /// two of them while a swipe moves between them.
+    pub fn rendered_on(&self, output: &Output) -> Vec<(&Workspace, Point<i32, Logical>)> {
+        let Some(active) = self.active_on(output) else {
+            return Vec::new();
+        };
+        let Some(swipe) = self
+            .swipe
+            .as_ref()
+            .filter(|swipe| &swipe.output == output && !swipe.is_settled())
+        else {
+            return vec![(active, Point::default())];
+        };
+
+        let offset = swipe.offset().round() as i32;
+        let mut rendered = vec![(active, Point::from((offset, 0)))];
+        if let Some(other) = swipe.other.and_then(|id| self.workspaces.get(id as usize)) {
+            let width = output_geometry(output).map_or(0, |geometry| geometry.size.w);
+            let x = if offset > 0 { offset - width } else { offset + width };
+            rendered.push((other, Point::from((x, 0))));
+        }
+        rendered
+    }
+
+    pub fn is_visible(&self, id: u8) -> bool {
+        self.outputs.iter().any(|(_, active)| *active == Some(id))
+    }
+
+    pub fn output_under(&self, point: Point<f64, Logical>) -> Option<&Output> {
+        self.outputs().find(|output| {
+            output_geometry(output).map_or(false, |geometry| geometry.to_f64().contains(point))
+        })
+    }
+
+    /// Adds an output to the layout, at its configured position or to the right
+    /// of the connected outputs, and shows a hidden workspace on it,
+    /// preferring the ones bound to the output.
+    pub fn add_output(&mut self, output: Output, config: Option<&OutputConfig>, gaps: (i32, i32)) {
+        if self.outputs().any(|o| o == &output) {
+            return;
+        }
+        let position = config.and_then(|c| c.position()).unwrap_or_else(|| {
+            let x = self
+                .outputs()
+                .filter_map(output_geometry)
+                .map(|geometry| geometry.loc.x + geometry.size.w)
+                .max()
+                .unwrap_or(0);
+            (x, 0).into()
+        });
+        output.change_current_state(None, None, None, Some(position));
+
+        let bound: Vec<u8> = config
+            .map(|c| c.workspaces().to_vec())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|id| (*id as usize) < self.workspaces.len())
+            .collect();
+        for id in &bound {
+            self.workspaces[*id as usize].bound_output = Some(output.name());
+        }
+
+        // workspaces without an output, and hidden ones bound to this output, are laid out on it
+        for id in 0..self.workspaces.len() as u8 {
+            let workspace = &self.workspaces[id as usize];
+            if workspace.output.is_none() || (bound.contains(&id) && !self.is_visible(id)) {
+                let workspace = &mut self.workspaces[id as usize];
+                workspace.set_output(Some(output.clone()));
+                update_layout(workspace, gaps);
+            }
+        }
+
+        let shown = bound
+            .iter()
+            .copied()
+            .chain(0..self.workspaces.len() as u8)
+            .find(|id| !self.is_visible(*id));
+        if let Some(id) = shown {
+            let workspace = &mut self.workspaces[id as usize];
+            if workspace.output() != Some(&output) {
+                workspace.set_output(Some(output.clone()));
+                update_layout(workspace, gaps);
+            }
+        }
+        self.outputs.push((output, shown));
+
+        if !self.is_visible(self.current) {
+            if let Some(id) = shown {
+                self.current = id;
+            }
+        }
+    }
+
+    /// Removes an output from the layout, moving its workspaces to the first remaining output.
+    pub fn remove_output(&mut self, output: &Output, gaps: (i32, i32)) {
+        let Some(index) = self.outputs.iter().position(|(o, _)| o == output) else {
+            return;
+        };
+        self.outputs.remove(index);
+
+        let fallback = self.outputs.first().map(|(o, _)| o.clone());
+        for workspace in self.workspaces.iter_mut() {
+            if workspace.output() == Some(output) {
+                workspace.set_output(fallback.clone());
+                update_layout(workspace, gaps);
+            }
+        }
+
+        if !self.is_visible(self.current) {
+            if let Some(id) = self.outputs.iter().find_map(|(_, active)| *active) {
+                self.current = id;
+            }
+        }
+    }
+
+    /// Shows a workspace and gives it focus.
+    ///
+    /// Hidden workspaces appear on the output they are bound to if it is connected,
+    /// on the focused output otherwise.
+    pub fn activate(&mut self, id: u8, magma_ipc_manager: &mut MagmaIpcManager, gaps: (i32, i32)) {
+        if id as usize >= self.workspaces.len() {
+            return;
+        }
+        self.swipe = None;
+        if !self.is_visible(id) {
+            let bound = self.workspaces[id as usize]
+                .bound_output
+                .as_ref()
+                .and_then(|name| self.outputs.iter().position(|(o, _)| &o.name() == name));
+            let target = bound.or_else(|| {
+                self.outputs
+                    .iter()
+                    .position(|(_, active)| *active == Some(self.current))
+            });
+            if let Some(index) = target {
+                let output = self.outputs[index].0.clone();
+                self.arrange_on(id, &output, gaps);
+                self.outputs[index].1 = Some(id);
+            }
+        }
+        self.current = id;
+        magma_ipc_manager.update_active_workspace(id.into());
+    }
+
+    /// Focuses the workspace shown on `output`.
+    pub fn focus_output(&mut self, output: &Output, magma_ipc_manager: &mut MagmaIpcManager) {
+        if let Some(id) = self.active_id_on(output) {
+            if id != self.current {
+                self.current = id;
+                magma_ipc_manager.update_active_workspace(id.into());
+            }
+        }
+    }
+
+    /// The output next to the focused one in the given direction.
+    pub fn output_in_direction(&self, direction: Direction) -> Option<Output> {
+        let current = self.current_output()?;
+        let from = output_geometry(current)?;
+        closest_in_direction(
+            from,
+            self.outputs()
+                .filter(|o| *o != current)
+                .filter_map(|o| output_geometry(o).map(|geometry| (geometry, o.clone()))),
+            direction,
+        )
+    }
+
+    /// Moves the current workspace to the output in the given direction.
+    ///
+    /// The output it leaves shows a hidden workspace laid out on it,
+    /// or the workspace the other output showed before.
+    pub fn move_current_to_output(&mut self, direction: Direction, gaps: (i32, i32)) {
+        let (Some(from), Some(to)) = (self.current_output().cloned(), self.output_in_direction(direction)) else {
+            return;
+        };
+        let from_index = self.outputs.iter().position(|(o, _)| o == &from).unwrap();
+        let to_index = self.outputs.iter().position(|(o, _)| o == &to).unwrap();
+        let replaced = self.outputs[to_index].1;
+
+        let workspace = &mut self.workspaces[self.current as usize];
+        workspace.set_output(Some(to));
+        update_layout(workspace, gaps);
+        self.outputs[to_index].1 = Some(self.current);
+        self.outputs[from_index].1 = None;
+
+        let replacement = (0..self.workspaces.len() as u8)
+            .find(|id| !self.is_visible(*id) && self.workspaces[*id as usize].output() == Some(&from))
+            .or(replaced);
+        if let Some(id) = replacement {
+            let workspace = &mut self.workspaces[id as usize];
+            if workspace.output() != Some(&from) {
+                workspace.set_output(Some(from));
+                update_layout(workspace, gaps);
+            }
+        }
+        self.outputs[from_index].1 = replacement;
+    }
+
     pub fn all_windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
         self.workspaces.iter().flat_map(|w| w.windows())
     }
@@ -195,19 +1220,43 @@ impl Workspaces {
             .find(|w| w.contains_window(window))
     }
 
-    pub fn activate(&mut self, id: u8, magma_ipc_manager: &mut MagmaIpcManager) {
-        self.current = id;
-        magma_ipc_manager.update_active_workspace(id.into());
+    /// Sets the fullscreen state of a window on any workspace and updates its layout.
+    pub fn set_fullscreen(&mut self, window: &Window, fullscreen: bool, gaps: (i32, i32)) {
+        if let Some(ws) = self.workspace_from_window(window) {
+            ws.set_fullscreen(window, fullscreen);
+            update_layout(ws, gaps);
+        }
     }
+
+    /// Sets the maximized state of a window on any workspace and updates its layout.
+    pub fn set_maximized(&mut self, window: &Window, maximized: bool, gaps: (i32, i32)) {
+        if let Some(ws) = self.workspace_from_window(window) {
+            ws.set_maximized(window, maximized);
+            update_layout(ws, gaps);
+        }
+    }
+
     pub fn move_window_to_workspace(&mut self, window: &Window, workspace: u8, gaps: (i32, i32)) {
         let mut removed = None;
+        let mut floating = false;
+        let mut origin = Point::default();
         if let Some(ws) = self.workspace_from_window(window) {
+            floating = ws.is_floating(window);
+            origin = ws.output_location();
             removed = ws.remove_window(window);
-            bsp_update_layout(ws, gaps)
+            update_layout(ws, gaps)
         }
         if let Some(removed) = removed {
-            self.workspaces[workspace as usize].add_window(removed);
-            bsp_update_layout(&mut self.workspaces[workspace as usize], gaps)
+            if floating {
+                // keep the position relative to the output
+                let offset = self.workspaces[workspace as usize].output_location() - origin;
+                removed.borrow_mut().rec.loc += offset;
+                self.workspaces[workspace as usize].add_floating_window(removed);
+            } else {
+                self.workspaces[workspace as usize].add_window(removed);
+            }
+            self.workspaces[workspace as usize].set_focused(window.clone());
+            update_layout(&mut self.workspaces[workspace as usize], gaps)
         }
     }
 }
@@ -0,0 +1,84 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{rect, Fixture, TEST_CONFIG};
+use magma::cli::Args;
+
+fn parse(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn parses_options() {
+    let args = parse(&[
+        "--headless",
+        "--config",
+        "/tmp/magma.ron",
+        "--socket",
+        "magma-1",
+        "--log",
+        "magma=debug",
+    ])
+    .unwrap();
+
+    assert_eq!(args.backend.as_deref(), Some("--headless"));
+    assert_eq!(args.config, Some(PathBuf::from("/tmp/magma.ron")));
+    assert_eq!(args.socket.as_deref(), Some("magma-1"));
+    assert_eq!(args.log.as_deref(), Some("magma=debug"));
+    assert!(!args.check_config);
+}
+
+#[test]
+fn parses_flags() {
+    let args = parse(&["--check-config", "--version"]).unwrap();
+
+    assert!(args.check_config);
+    assert!(args.version);
+    assert_eq!(args.backend, None);
+}
+
+#[test]
+fn parses_check_config_subcommand() {
+    let args = parse(&["check-config", "--config", "/tmp/magma.ron"]).unwrap();
+
+    assert!(args.check_config);
+    assert_eq!(args.config, Some(PathBuf::from("/tmp/magma.ron")));
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    assert_eq!(parse(&["--config"]).unwrap_err(), "--config needs a value");
+    assert_eq!(
+        parse(&["--socket", "--winit"]).unwrap_err(),
+        "--socket needs a value"
+    );
+    assert_eq!(parse(&["--frobnicate"]).unwrap_err(), "Unknown option: --frobnicate");
+    assert_eq!(
+        parse(&["--winit", "--headless"]).unwrap_err(),
+        "Conflicting backends: --winit and --headless"
+    );
+}
+
+#[test]
+fn uses_config_and_socket_from_args() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("custom.ron");
+    std::fs::write(&config, TEST_CONFIG.replace("gaps: (5, 5)", "gaps: (10, 10)")).unwrap();
+    let socket = format!("magma-test-{}", std::process::id());
+
+    let mut f = Fixture::with_args(
+        &[(1920, 1080)],
+        &Args {
+            config: Some(config.clone()),
+            socket: Some(socket.clone()),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(f.data.state.socket_name, socket.as_str());
+    assert_eq!(f.data.state.config_path, Some(config));
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(20, 20, 1880, 1040));
+}
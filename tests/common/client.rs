@@ -0,0 +1,555 @@
+//! A minimal wayland client living in the test process.
+
+use std::{
+    fs::File,
+    io::ErrorKind,
+    os::{fd::AsRawFd, unix::net::UnixStream},
+};
+
+use wayland_client::{
+    backend::WaylandError,
+    protocol::{
+        wl_buffer::WlBuffer,
+        wl_compositor::WlCompositor,
+        wl_keyboard::{self, WlKeyboard},
+        wl_pointer::{self, WlPointer},
+        wl_registry::{self, WlRegistry},
+        wl_seat::{self, WlSeat},
+        wl_shm::{self, WlShm},
+        wl_shm_pool::WlShmPool,
+        wl_surface::WlSurface,
+        wl_touch::{self, WlTouch},
+    },
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols::xdg::shell::client::{
+    xdg_surface::{self, XdgSurface},
+    xdg_toplevel::{self, ResizeEdge, XdgToplevel},
+    xdg_wm_base::{self, XdgWmBase},
+};
+use wayland_protocols_wlr::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::{Layer, ZwlrLayerShellV1},
+    zwlr_layer_surface_v1::{self, Anchor, ZwlrLayerSurfaceV1},
+};
+
+use self::ipc::{
+    keybindings::{Event as KeybindingsEvent, Keybindings},
+    magma_ipc::MagmaIpc,
+    workspaces::{Event as WorkspacesEvent, Workspaces},
+};
+
+pub mod ipc {
+    use wayland_client;
+
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!("ipc.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("ipc.xml");
+}
+
+pub struct TestClient {
+    pub connection: Connection,
+    queue: EventQueue<ClientState>,
+    pub state: ClientState,
+}
+
+#[derive(Default)]
+pub struct ClientState {
+    compositor: Option<WlCompositor>,
+    shm: Option<WlShm>,
+    wm_base: Option<XdgWmBase>,
+    layer_shell: Option<ZwlrLayerShellV1>,
+    ipc: Option<MagmaIpc>,
+    seat: Option<WlSeat>,
+    pointer: Option<WlPointer>,
+    keyboard: Option<WlKeyboard>,
+    touch: Option<WlTouch>,
+    buffers: Vec<File>,
+
+    /// Serial of the last pointer button event.
+    pub button_serial: Option<u32>,
+
+    pub toplevels: Vec<TestToplevel>,
+    pub layers: Vec<TestLayer>,
+    pub active_workspace: Option<u32>,
+    pub occupied_workspaces: Option<Vec<u8>>,
+    pub active_mode: Option<String>,
+    /// Last `pending_keys` event of the keybindings subscription.
+    pub pending_keys: Option<String>,
+    /// Key events received while one of the client's surfaces had keyboard focus.
+    pub keys: Vec<(u32, wl_keyboard::KeyState)>,
+    /// Key repeat delay and rate of the last `repeat_info` event.
+    pub repeat_info: Option<(i32, i32)>,
+    /// Touch points by id, with their last surface-local position while down.
+    pub touch_points: Vec<(i32, Option<(f64, f64)>)>,
+}
+
+pub struct TestToplevel {
+    pub surface: WlSurface,
+    pub xdg_surface: XdgSurface,
+    pub toplevel: XdgToplevel,
+    /// Size of the last configure that was acked and committed.
+    pub size: Option<(i32, i32)>,
+    /// States of the last configure.
+    pub states: Vec<xdg_toplevel::State>,
+    pub configures: usize,
+    pub closed: bool,
+    pending_size: (i32, i32),
+    pending_states: Vec<xdg_toplevel::State>,
+}
+
+pub struct TestLayer {
+    pub surface: WlSurface,
+    pub layer_surface: ZwlrLayerSurfaceV1,
+    pub size: Option<(i32, i32)>,
+}
+
+impl TestClient {
+    pub fn new(stream: UnixStream) -> Self {
+        let connection = Connection::from_socket(stream).unwrap();
+        let queue = connection.new_event_queue();
+        connection.display().get_registry(&queue.handle(), ());
+
+        TestClient {
+            connection,
+            queue,
+            state: ClientState::default(),
+        }
+    }
+
+    pub fn flush(&mut self) {
+        self.connection.flush().unwrap();
+    }
+
+    /// Reads whatever the server sent without blocking and dispatches it.
+    pub fn read_events(&mut self) {
+        if let Some(guard) = self.queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+                Err(err) => panic!("Failed to read events: {}", err),
+            }
+        }
+        self.queue.dispatch_pending(&mut self.state).unwrap();
+    }
+
+    /// Whether all globals required by the helpers have been bound.
+    pub fn is_ready(&self) -> bool {
+        self.state.compositor.is_some()
+            && self.state.shm.is_some()
+            && self.state.wm_base.is_some()
+            && self.state.layer_shell.is_some()
+            && self.state.ipc.is_some()
+            && self.state.pointer.is_some()
+            && self.state.keyboard.is_some()
+    }
+
+    /// Creates a xdg toplevel and returns its index.
+    pub fn create_toplevel(&mut self) -> usize {
+        self.create_toplevel_with(|_| {})
+    }
+
+    /// Creates a xdg toplevel, letting `setup` set its state before the initial commit.
+    pub fn create_toplevel_with(&mut self, setup: impl FnOnce(&XdgToplevel)) -> usize {
+        let qh = self.queue.handle();
+        let index = self.state.toplevels.len();
+
+        let surface = self.state.compositor.as_ref().unwrap().create_surface(&qh, ());
+        let xdg_surface = self
+            .state
+            .wm_base
+            .as_ref()
+            .unwrap()
+            .get_xdg_surface(&surface, &qh, Role::Toplevel(index));
+        let toplevel = xdg_surface.get_toplevel(&qh, index);
+        toplevel.set_title(format!("toplevel {}", index));
+        setup(&toplevel);
+        surface.commit();
+
+        self.state.toplevels.push(TestToplevel {
+            surface,
+            xdg_surface,
+            toplevel,
+            size: None,
+            states: Vec::new(),
+            configures: 0,
+            closed: false,
+            pending_size: (0, 0),
+            pending_states: Vec::new(),
+        });
+        index
+    }
+
+    /// Destroys a xdg toplevel, unmapping it.
+    pub fn destroy_toplevel(&mut self, index: usize) {
+        let toplevel = &self.state.toplevels[index];
+        toplevel.toplevel.destroy();
+        toplevel.xdg_surface.destroy();
+        toplevel.surface.destroy();
+    }
+
+    /// Unmaps a xdg toplevel by committing a null buffer and maps it again at its last size.
+    pub fn remap_toplevel(&mut self, index: usize) {
+        let qh = self.queue.handle();
+        let toplevel = &self.state.toplevels[index];
+        let surface = toplevel.surface.clone();
+        let size = toplevel.size.unwrap();
+        surface.attach(None, 0, 0);
+        surface.commit();
+        self.state.attach_buffer(&surface, size, &qh);
+    }
+
+    /// Creates a layer surface on the default output and returns its index.
+    pub fn create_layer(&mut self, layer: Layer, anchor: Anchor, exclusive_zone: i32, size: (u32, u32)) -> usize {
+        let qh = self.queue.handle();
+        let index = self.state.layers.len();
+
+        let surface = self.state.compositor.as_ref().unwrap().create_surface(&qh, ());
+        let layer_surface = self.state.layer_shell.as_ref().unwrap().get_layer_surface(
+            &surface,
+            None,
+            layer,
+            "test".to_string(),
+            &qh,
+            index,
+        );
+        layer_surface.set_anchor(anchor);
+        layer_surface.set_exclusive_zone(exclusive_zone);
+        layer_surface.set_size(size.0, size.1);
+        surface.commit();
+
+        self.state.layers.push(TestLayer {
+            surface,
+            layer_surface,
+            size: None,
+        });
+        index
+    }
+
+    /// Asks to move a toplevel interactively, using the last button press as trigger.
+    pub fn move_toplevel(&mut self, index: usize) {
+        let seat = self.state.seat.as_ref().unwrap();
+        let serial = self.state.button_serial.unwrap();
+        self.state.toplevels[index].toplevel._move(seat, serial);
+    }
+
+    /// Asks to resize a toplevel interactively, using the last button press as trigger.
+    pub fn resize_toplevel(&mut self, index: usize, edge: ResizeEdge) {
+        let seat = self.state.seat.as_ref().unwrap();
+        let serial = self.state.button_serial.unwrap();
+        self.state.toplevels[index].toplevel.resize(seat, serial, edge);
+    }
+
+    /// Subscribes to magma's keybinding events.
+    pub fn subscribe_keybindings(&mut self) {
+        let qh = self.queue.handle();
+        self.state.ipc.as_ref().unwrap().keybindings(&qh, ());
+    }
+
+    /// Subscribes to magma's workspace events.
+    pub fn subscribe_workspaces(&mut self) {
+        let qh = self.queue.handle();
+        self.state.ipc.as_ref().unwrap().workspaces(&qh, ());
+    }
+}
+
+impl ClientState {
+    /// Attaches a freshly allocated shm buffer of the given size and commits.
+    fn attach_buffer(&mut self, surface: &WlSurface, size: (i32, i32), qh: &QueueHandle<Self>) {
+        let (w, h) = size;
+        let stride = w * 4;
+        let file = tempfile::tempfile().unwrap();
+        file.set_len((stride * h) as u64).unwrap();
+
+        let pool = self
+            .shm
+            .as_ref()
+            .unwrap()
+            .create_pool(file.as_raw_fd(), stride * h, qh, ());
+        let buffer = pool.create_buffer(0, w, h, stride, wl_shm::Format::Argb8888, qh, ());
+        pool.destroy();
+
+        surface.attach(Some(&buffer), 0, 0);
+        surface.damage_buffer(0, 0, w, h);
+        surface.commit();
+        self.buffers.push(file);
+    }
+}
+
+/// User data of a `XdgSurface`, pointing to the role object it belongs to.
+pub enum Role {
+    Toplevel(usize),
+}
+
+impl Dispatch<WlRegistry, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            match &interface[..] {
+                "wl_compositor" => {
+                    state.compositor = Some(registry.bind(name, version.min(4), qh, ()));
+                }
+                "wl_shm" => state.shm = Some(registry.bind(name, 1, qh, ())),
+                "xdg_wm_base" => state.wm_base = Some(registry.bind(name, version.min(3), qh, ())),
+                "zwlr_layer_shell_v1" => {
+                    state.layer_shell = Some(registry.bind(name, version.min(4), qh, ()))
+                }
+                "magma_ipc" => state.ipc = Some(registry.bind(name, 1, qh, ())),
+                "wl_seat" if state.seat.is_none() => {
+                    state.seat = Some(registry.bind(name, version.min(5), qh, ()))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for ClientState {
+    fn event(
+        _state: &mut Self,
+        wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, Role> for ClientState {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &XdgSurface,
+        event: xdg_surface::Event,
+        role: &Role,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            match role {
+                Role::Toplevel(index) => {
+                    let toplevel = &mut state.toplevels[*index];
+                    toplevel.configures += 1;
+                    toplevel.states = std::mem::take(&mut toplevel.pending_states);
+                    let (w, h) = toplevel.pending_size;
+                    let size = (if w > 0 { w } else { 100 }, if h > 0 { h } else { 100 });
+                    toplevel.size = Some(size);
+                    let surface = toplevel.surface.clone();
+                    state.attach_buffer(&surface, size, qh);
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, usize> for ClientState {
+    fn event(
+        state: &mut Self,
+        _toplevel: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        index: &usize,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let toplevel = &mut state.toplevels[*index];
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
+                toplevel.pending_size = (width, height);
+                toplevel.pending_states = states
+                    .chunks_exact(4)
+                    .filter_map(|chunk| {
+                        let state = u32::from_ne_bytes(chunk.try_into().unwrap());
+                        xdg_toplevel::State::try_from(state).ok()
+                    })
+                    .collect();
+            }
+            xdg_toplevel::Event::Close => toplevel.closed = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrLayerSurfaceV1, usize> for ClientState {
+    fn event(
+        state: &mut Self,
+        layer_surface: &ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        index: &usize,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_layer_surface_v1::Event::Configure {
+            serial,
+            width,
+            height,
+        } = event
+        {
+            layer_surface.ack_configure(serial);
+            let size = (width.max(1) as i32, height.max(1) as i32);
+            let layer = &mut state.layers[*index];
+            layer.size = Some(size);
+            let surface = layer.surface.clone();
+            state.attach_buffer(&surface, size, qh);
+        }
+    }
+}
+
+impl Dispatch<WlSeat, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        seat: &WlSeat,
+        event: wl_seat::Event,
+        _data: &(),
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_seat::Event::Capabilities {
+            capabilities: WEnum::Value(capabilities),
+        } = event
+        {
+            if capabilities.contains(wl_seat::Capability::Pointer) && state.pointer.is_none() {
+                state.pointer = Some(seat.get_pointer(qh, ()));
+            }
+            if capabilities.contains(wl_seat::Capability::Keyboard) && state.keyboard.is_none() {
+                state.keyboard = Some(seat.get_keyboard(qh, ()));
+            }
+            if capabilities.contains(wl_seat::Capability::Touch) && state.touch.is_none() {
+                state.touch = Some(seat.get_touch(qh, ()));
+            }
+        }
+    }
+}
+
+impl Dispatch<WlPointer, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _pointer: &WlPointer,
+        event: wl_pointer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_pointer::Event::Button { serial, .. } = event {
+            state.button_serial = Some(serial);
+        }
+    }
+}
+
+impl Dispatch<WlTouch, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _touch: &WlTouch,
+        event: wl_touch::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let (id, position) = match event {
+            wl_touch::Event::Down { id, x, y, .. } | wl_touch::Event::Motion { id, x, y, .. } => {
+                (id, Some((x, y)))
+            }
+            wl_touch::Event::Up { id, .. } => (id, None),
+            _ => return,
+        };
+        match state.touch_points.iter_mut().find(|(point, _)| *point == id) {
+            Some(point) => point.1 = position,
+            None => state.touch_points.push((id, position)),
+        }
+    }
+}
+
+impl Dispatch<Workspaces, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _proxy: &Workspaces,
+        event: WorkspacesEvent,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            WorkspacesEvent::ActiveWorkspace { id } => state.active_workspace = Some(id),
+            WorkspacesEvent::OccupiedWorkspaces { occupied } => {
+                state.occupied_workspaces = Some(occupied)
+            }
+        }
+    }
+}
+
+impl Dispatch<WlKeyboard, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _keyboard: &WlKeyboard,
+        event: wl_keyboard::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Key {
+                key,
+                state: WEnum::Value(key_state),
+                ..
+            } => state.keys.push((key, key_state)),
+            wl_keyboard::Event::RepeatInfo { rate, delay } => state.repeat_info = Some((delay, rate)),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<Keybindings, ()> for ClientState {
+    fn event(
+        state: &mut Self,
+        _proxy: &Keybindings,
+        event: KeybindingsEvent,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            KeybindingsEvent::ActiveMode { name } => state.active_mode = Some(name),
+            KeybindingsEvent::PendingKeys { keys } => state.pending_keys = Some(keys),
+        }
+    }
+}
+
+macro_rules! ignore_events {
+    ($($proxy:ty),*) => {
+        $(
+            impl Dispatch<$proxy, ()> for ClientState {
+                fn event(
+                    _state: &mut Self,
+                    _proxy: &$proxy,
+                    _event: <$proxy as Proxy>::Event,
+                    _data: &(),
+                    _conn: &Connection,
+                    _qh: &QueueHandle<Self>,
+                ) {
+                }
+            }
+        )*
+    };
+}
+
+ignore_events!(WlCompositor, WlSurface, WlShm, WlShmPool, WlBuffer, ZwlrLayerShellV1, MagmaIpc);
@@ -0,0 +1,337 @@
+//! Synthetic input devices used to inject events into `process_input_event`.
+
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
+    GestureBeginEvent, GestureEndEvent, GestureSwipeBeginEvent, GestureSwipeEndEvent,
+    GestureSwipeUpdateEvent, InputBackend, KeyState, KeyboardKeyEvent, PointerAxisEvent,
+    PointerButtonEvent, PointerMotionEvent, TouchDownEvent, TouchEvent, TouchMotionEvent,
+    TouchSlot, TouchUpEvent, UnusedEvent,
+};
+
+pub struct TestInput;
+
+impl InputBackend for TestInput {
+    type Device = TestDevice;
+    type KeyboardKeyEvent = TestKeyEvent;
+    type PointerAxisEvent = TestAxisEvent;
+    type PointerButtonEvent = TestButtonEvent;
+    type PointerMotionEvent = TestMotionEvent;
+    type PointerMotionAbsoluteEvent = UnusedEvent;
+    type GestureSwipeBeginEvent = TestSwipeBeginEvent;
+    type GestureSwipeUpdateEvent = TestSwipeUpdateEvent;
+    type GestureSwipeEndEvent = TestSwipeEndEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+    type TouchDownEvent = TestTouchEvent;
+    type TouchUpEvent = TestTouchUpEvent;
+    type TouchMotionEvent = TestTouchEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
+    type SpecialEvent = UnusedEvent;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestDevice;
+
+impl Device for TestDevice {
+    fn id(&self) -> String {
+        "test".to_string()
+    }
+
+    fn name(&self) -> String {
+        "Magma test device".to_string()
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Keyboard
+                | DeviceCapability::Pointer
+                | DeviceCapability::Gesture
+                | DeviceCapability::Touch
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+pub struct TestKeyEvent {
+    pub time: u64,
+    pub key: u32,
+    pub state: KeyState,
+}
+
+impl Event<TestInput> for TestKeyEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl KeyboardKeyEvent<TestInput> for TestKeyEvent {
+    fn key_code(&self) -> u32 {
+        self.key
+    }
+
+    fn state(&self) -> KeyState {
+        self.state
+    }
+
+    fn count(&self) -> u32 {
+        match self.state {
+            KeyState::Pressed => 1,
+            KeyState::Released => 0,
+        }
+    }
+}
+
+pub struct TestButtonEvent {
+    pub time: u64,
+    pub button: u32,
+    pub state: ButtonState,
+}
+
+impl Event<TestInput> for TestButtonEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl PointerButtonEvent<TestInput> for TestButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+pub struct TestMotionEvent {
+    pub time: u64,
+    pub delta: (f64, f64),
+}
+
+impl Event<TestInput> for TestMotionEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl PointerMotionEvent<TestInput> for TestMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta.1
+    }
+}
+
+/// Vertical scrolling by `steps` wheel detents, negative steps scroll up.
+pub struct TestAxisEvent {
+    pub time: u64,
+    pub steps: f64,
+}
+
+impl Event<TestInput> for TestAxisEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl PointerAxisEvent<TestInput> for TestAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::Vertical => Some(self.steps * 15.0),
+            Axis::Horizontal => None,
+        }
+    }
+
+    fn amount_discrete(&self, axis: Axis) -> Option<f64> {
+        match axis {
+            Axis::Vertical => Some(self.steps),
+            Axis::Horizontal => None,
+        }
+    }
+
+    fn source(&self) -> AxisSource {
+        AxisSource::Wheel
+    }
+}
+
+pub struct TestSwipeBeginEvent {
+    pub time: u64,
+    pub fingers: u32,
+}
+
+impl Event<TestInput> for TestSwipeBeginEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl GestureBeginEvent<TestInput> for TestSwipeBeginEvent {
+    fn fingers(&self) -> u32 {
+        self.fingers
+    }
+}
+
+impl GestureSwipeBeginEvent<TestInput> for TestSwipeBeginEvent {}
+
+pub struct TestSwipeUpdateEvent {
+    pub time: u64,
+    pub delta: (f64, f64),
+}
+
+impl Event<TestInput> for TestSwipeUpdateEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl GestureSwipeUpdateEvent<TestInput> for TestSwipeUpdateEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+}
+
+pub struct TestSwipeEndEvent {
+    pub time: u64,
+    pub cancelled: bool,
+}
+
+impl Event<TestInput> for TestSwipeEndEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl GestureEndEvent<TestInput> for TestSwipeEndEvent {
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl GestureSwipeEndEvent<TestInput> for TestSwipeEndEvent {}
+
+/// A touch point going down or moving, `position` is given as a fraction of
+/// the output size.
+pub struct TestTouchEvent {
+    pub time: u64,
+    pub slot: u32,
+    pub position: (f64, f64),
+}
+
+impl Event<TestInput> for TestTouchEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl TouchEvent<TestInput> for TestTouchEvent {
+    fn slot(&self) -> TouchSlot {
+        Some(self.slot).into()
+    }
+}
+
+impl AbsolutePositionEvent<TestInput> for TestTouchEvent {
+    fn x(&self) -> f64 {
+        self.position.0
+    }
+
+    fn y(&self) -> f64 {
+        self.position.1
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.position.0 * width as f64
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.position.1 * height as f64
+    }
+}
+
+impl TouchDownEvent<TestInput> for TestTouchEvent {}
+impl TouchMotionEvent<TestInput> for TestTouchEvent {}
+
+pub struct TestTouchUpEvent {
+    pub time: u64,
+    pub slot: u32,
+}
+
+impl Event<TestInput> for TestTouchUpEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> TestDevice {
+        TestDevice
+    }
+}
+
+impl TouchEvent<TestInput> for TestTouchUpEvent {
+    fn slot(&self) -> TouchSlot {
+        Some(self.slot).into()
+    }
+}
+
+impl TouchUpEvent<TestInput> for TestTouchUpEvent {}
@@ -0,0 +1,444 @@
+//! Test harness booting magma on the headless backend inside the test process.
+//!
+//! Clients are connected through socket pairs and driven from the same thread,
+//! so every helper interleaves server and client dispatching until a condition holds.
+#![allow(dead_code)]
+
+use std::{
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    sync::{Arc, Once},
+    time::Duration,
+};
+
+use magma::{
+    backends::headless::{init_headless_state, HeadlessData},
+    cli::Args,
+    config::{Action, Config, ConfigError},
+    state::{CalloopData, ClientState as ServerClientState},
+    utils::focus::FocusTarget,
+};
+use smithay::{
+    backend::input::{ButtonState, InputEvent, KeyState},
+    desktop::Window,
+    output::Output,
+    reexports::{calloop::EventLoop, wayland_server::Resource},
+    utils::{Logical, Point, Rectangle},
+};
+use wayland_client::Proxy;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+
+use self::{
+    client::TestClient,
+    input::{
+        TestAxisEvent, TestButtonEvent, TestInput, TestKeyEvent, TestMotionEvent,
+        TestSwipeBeginEvent, TestSwipeEndEvent, TestSwipeUpdateEvent, TestTouchEvent,
+        TestTouchUpEvent,
+    },
+};
+
+pub mod client;
+pub mod input;
+
+/// Evdev key codes used by the test configuration.
+pub mod keys {
+    pub const SUPER: u32 = 125;
+    pub const SHIFT: u32 = 42;
+    pub const CTRL: u32 = 29;
+    pub const KEY_1: u32 = 2;
+    pub const KEY_2: u32 = 3;
+    pub const Q: u32 = 16;
+    pub const TAB: u32 = 15;
+    pub const H: u32 = 35;
+    pub const J: u32 = 36;
+    pub const K: u32 = 37;
+    pub const L: u32 = 38;
+    pub const LEFT: u32 = 105;
+    pub const RIGHT: u32 = 106;
+    pub const SPACE: u32 = 57;
+    pub const F: u32 = 33;
+    pub const M: u32 = 50;
+    pub const I: u32 = 23;
+    pub const O: u32 = 24;
+    pub const P: u32 = 25;
+    pub const R: u32 = 19;
+    pub const ESC: u32 = 1;
+    pub const W: u32 = 17;
+    pub const G: u32 = 34;
+    pub const X: u32 = 45;
+    pub const ALT: u32 = 56;
+    pub const U: u32 = 22;
+    pub const CAPS: u32 = 58;
+    pub const T: u32 = 20;
+}
+
+/// Evdev button codes.
+pub const BTN_LEFT: u32 = 0x110;
+pub const BTN_RIGHT: u32 = 0x111;
+pub const BTN_MIDDLE: u32 = 0x112;
+
+/// Upper bound of dispatch rounds before `wait_until` gives up.
+const MAX_ROUNDS: usize = 100;
+
+pub static TEST_CONFIG: &str = include_str!("config.ron");
+static INIT: Once = Once::new();
+
+/// Parses and validates `contents` as if it were read from a config file.
+pub fn load(contents: &str) -> Result<Config, ConfigError> {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), contents).unwrap();
+    Config::from_path(file.path())
+}
+
+/// Points `XDG_CONFIG_HOME` to a directory containing the test configuration
+/// and makes sure a runtime directory for the wayland socket exists.
+fn init_env() {
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("magma-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("magma.ron"), TEST_CONFIG).unwrap();
+        std::env::set_var("XDG_CONFIG_HOME", &dir);
+
+        if std::env::var_os("XDG_RUNTIME_DIR").is_none() {
+            let runtime_dir: PathBuf = dir.join("runtime");
+            std::fs::create_dir_all(&runtime_dir).unwrap();
+            std::env::set_var("XDG_RUNTIME_DIR", runtime_dir);
+        }
+    });
+}
+
+pub struct Fixture {
+    pub event_loop: EventLoop<'static, CalloopData<HeadlessData>>,
+    pub data: CalloopData<HeadlessData>,
+    clients: Vec<TestClient>,
+    time: u64,
+}
+
+impl Fixture {
+    /// Boots magma with a single 1920x1080 output.
+    pub fn new() -> Self {
+        Self::with_outputs(&[(1920, 1080)])
+    }
+
+    pub fn with_outputs(sizes: &[(i32, i32)]) -> Self {
+        Self::with_args(sizes, &Args::default())
+    }
+
+    /// Boots magma as if started with the given command line options.
+    pub fn with_args(sizes: &[(i32, i32)], args: &Args) -> Self {
+        init_env();
+        let mut event_loop = EventLoop::try_new().unwrap();
+        let sizes: Vec<_> = sizes.iter().map(|&size| size.into()).collect();
+        let data = init_headless_state(&mut event_loop, &sizes, args);
+
+        Fixture {
+            event_loop,
+            data,
+            clients: Vec::new(),
+            time: 0,
+        }
+    }
+
+    /// Connects a new client and waits until it has bound all globals.
+    pub fn add_client(&mut self) -> usize {
+        let (server, client) = UnixStream::pair().unwrap();
+        self.data
+            .display
+            .handle()
+            .insert_client(server, Arc::new(ServerClientState))
+            .unwrap();
+        self.clients.push(TestClient::new(client));
+
+        let id = self.clients.len() - 1;
+        self.wait_until(|f| f.client(id).is_ready());
+        id
+    }
+
+    pub fn client(&mut self, id: usize) -> &mut TestClient {
+        &mut self.clients[id]
+    }
+
+    /// Runs one round of client flushing, server dispatching and client reading.
+    pub fn dispatch(&mut self) {
+        for client in &mut self.clients {
+            client.flush();
+        }
+        self.data
+            .display
+            .dispatch_clients(&mut self.data.state)
+            .unwrap();
+        self.event_loop
+            .dispatch(Some(Duration::ZERO), &mut self.data)
+            .unwrap();
+        self.data.state.popup_manager.cleanup();
+        self.data.display.flush_clients().unwrap();
+        for client in &mut self.clients {
+            client.read_events();
+        }
+    }
+
+    /// Dispatches until no more progress is to be expected for pending requests.
+    pub fn roundtrip(&mut self) {
+        for _ in 0..4 {
+            self.dispatch();
+        }
+    }
+
+    /// Dispatches until `condition` holds, panicking after `MAX_ROUNDS`.
+    pub fn wait_until(&mut self, mut condition: impl FnMut(&mut Fixture) -> bool) {
+        for _ in 0..MAX_ROUNDS {
+            if condition(self) {
+                return;
+            }
+            self.dispatch();
+        }
+        panic!("Condition not met after {} dispatch rounds", MAX_ROUNDS);
+    }
+
+    /// Spawns `n` xdg toplevels and waits until every one of them is configured
+    /// and has committed a buffer of the configured size.
+    pub fn spawn_toplevels(&mut self, client: usize, n: usize) -> Vec<usize> {
+        let toplevels: Vec<usize> = (0..n)
+            .map(|_| {
+                let toplevel = self.client(client).create_toplevel();
+                self.wait_until(|f| f.client(client).state.toplevels[toplevel].size.is_some());
+                toplevel
+            })
+            .collect();
+        self.roundtrip();
+        toplevels
+    }
+
+    /// Spawns a toplevel set up by `setup` and waits until it committed a configured buffer.
+    pub fn spawn_toplevel_with(&mut self, client: usize, setup: impl FnOnce(&XdgToplevel)) -> usize {
+        let toplevel = self.client(client).create_toplevel_with(setup);
+        self.wait_until(|f| f.client(client).state.toplevels[toplevel].size.is_some());
+        self.roundtrip();
+        toplevel
+    }
+
+    /// Destroys a toplevel and waits until magma unmapped it.
+    pub fn destroy_toplevel(&mut self, client: usize, toplevel: usize) {
+        let window = self.window(client, toplevel);
+        self.client(client).destroy_toplevel(toplevel);
+        self.wait_until(|f| {
+            !f.data
+                .state
+                .workspaces
+                .all_windows()
+                .any(|w| *w == window)
+        });
+        self.roundtrip();
+    }
+
+    /// Returns the server side window of a client's toplevel.
+    pub fn window(&mut self, client: usize, toplevel: usize) -> Window {
+        let surface = self.client(client).state.toplevels[toplevel].surface.id();
+        self.data
+            .state
+            .workspaces
+            .all_windows()
+            .find(|w| {
+                let server_surface = w.toplevel().wl_surface();
+                server_surface.id().protocol_id() == surface.protocol_id()
+            })
+            .map(|w| w.clone())
+            .expect("toplevel is not mapped")
+    }
+
+    /// Rectangles of the windows of the current workspace, in mapping order.
+    pub fn window_rects(&self) -> Vec<Rectangle<i32, Logical>> {
+        self.data
+            .state
+            .workspaces
+            .current()
+            .magmawindows()
+            .map(|w| w.rec)
+            .collect()
+    }
+
+    /// Rectangle assigned to a client's toplevel.
+    pub fn window_rect(&mut self, client: usize, toplevel: usize) -> Rectangle<i32, Logical> {
+        let window = self.window(client, toplevel);
+        self.data
+            .state
+            .workspaces
+            .current()
+            .magmawindows()
+            .find(|w| w.window == window)
+            .map(|w| w.rec)
+            .expect("toplevel is not on the current workspace")
+    }
+
+    /// Writes `contents` to a new config file and reloads the config from it.
+    pub fn reload_config(&mut self, contents: &str) {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), contents).unwrap();
+        self.data.state.config_path = Some(file.path().to_path_buf());
+        self.data.state.handle_action(Action::ReloadConfig);
+        self.roundtrip();
+    }
+
+    /// The n-th virtual output.
+    pub fn output(&self, index: usize) -> Output {
+        self.data
+            .state
+            .backend_data
+            .outputs()
+            .nth(index)
+            .cloned()
+            .expect("no such output")
+    }
+
+    pub fn keyboard_focus(&self) -> Option<FocusTarget> {
+        self.data.state.seat.get_keyboard().unwrap().current_focus()
+    }
+
+    pub fn is_focused(&mut self, client: usize, toplevel: usize) -> bool {
+        let window = self.window(client, toplevel);
+        self.keyboard_focus() == Some(FocusTarget::Window(window))
+    }
+
+    fn next_time(&mut self) -> u64 {
+        self.time += 1000;
+        self.time
+    }
+
+    pub fn key(&mut self, key: u32, state: KeyState) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::Keyboard {
+                event: TestKeyEvent { time, key, state },
+            });
+        self.roundtrip();
+    }
+
+    /// Presses all `keys` in order and releases them in reverse order.
+    pub fn press_keys(&mut self, keys: &[u32]) {
+        for key in keys {
+            self.key(*key, KeyState::Pressed);
+        }
+        for key in keys.iter().rev() {
+            self.key(*key, KeyState::Released);
+        }
+    }
+
+    /// Moves the pointer to an absolute position using relative motion events.
+    pub fn move_pointer_to(&mut self, point: impl Into<Point<f64, Logical>>) {
+        let delta = point.into() - self.data.state.pointer_location;
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::PointerMotion {
+                event: TestMotionEvent {
+                    time,
+                    delta: (delta.x, delta.y),
+                },
+            });
+        self.roundtrip();
+    }
+
+    pub fn button(&mut self, button: u32, state: ButtonState) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::PointerButton {
+                event: TestButtonEvent {
+                    time,
+                    button,
+                    state,
+                },
+            });
+        self.roundtrip();
+    }
+
+    /// Scrolls the mouse wheel by `steps` detents, negative steps scroll up.
+    pub fn scroll(&mut self, steps: f64) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::PointerAxis {
+                event: TestAxisEvent { time, steps },
+            });
+        self.roundtrip();
+    }
+
+    pub fn swipe_begin(&mut self, fingers: u32) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::GestureSwipeBegin {
+                event: TestSwipeBeginEvent { time, fingers },
+            });
+    }
+
+    pub fn swipe_update(&mut self, delta: (f64, f64)) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::GestureSwipeUpdate {
+                event: TestSwipeUpdateEvent { time, delta },
+            });
+    }
+
+    pub fn swipe_end(&mut self, cancelled: bool) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::GestureSwipeEnd {
+                event: TestSwipeEndEvent { time, cancelled },
+            });
+        self.roundtrip();
+    }
+
+    /// A whole swipe with `fingers`, moving by `delta` in small steps.
+    pub fn swipe(&mut self, fingers: u32, delta: (f64, f64)) {
+        self.swipe_begin(fingers);
+        for _ in 0..10 {
+            self.swipe_update((delta.0 / 10.0, delta.1 / 10.0));
+        }
+        self.swipe_end(false);
+    }
+
+    /// Puts a finger down at `position`, a fraction of the touch screen's output.
+    pub fn touch_down(&mut self, slot: u32, position: (f64, f64)) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::TouchDown {
+                event: TestTouchEvent { time, slot, position },
+            });
+        self.roundtrip();
+    }
+
+    pub fn touch_motion(&mut self, slot: u32, position: (f64, f64)) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::TouchMotion {
+                event: TestTouchEvent { time, slot, position },
+            });
+        self.roundtrip();
+    }
+
+    pub fn touch_up(&mut self, slot: u32) {
+        let time = self.next_time();
+        self.data
+            .state
+            .process_input_event::<TestInput>(InputEvent::TouchUp {
+                event: TestTouchUpEvent { time, slot },
+            });
+        self.roundtrip();
+    }
+
+    pub fn click(&mut self, button: u32) {
+        self.button(button, ButtonState::Pressed);
+        self.button(button, ButtonState::Released);
+    }
+}
+
+/// Shorthand for building a logical rectangle in assertions.
+pub fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size((x, y), (w, h))
+}
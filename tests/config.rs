@@ -0,0 +1,355 @@
+mod common;
+
+use common::{keys, load, rect, Fixture, TEST_CONFIG};
+use magma::config::{Action, Config, ConfigError, Key};
+use smithay::utils::Size;
+
+#[test]
+fn reload_applies_gaps() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.reload_config(&TEST_CONFIG.replace("gaps: (5, 5)", "gaps: (10, 10)"));
+
+    assert_eq!(f.data.state.config.gaps, (10, 10));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(20, 20, 1880, 1040));
+    assert!(f.data.state.ipc_manager.config_error.is_none());
+}
+
+#[test]
+fn reload_applies_keybindings() {
+    let mut f = Fixture::new();
+
+    f.reload_config(&TEST_CONFIG.replace(
+        r#"(modifiers: [Super], key: "o"): FocusOutput(Right)"#,
+        r#"(modifiers: [Super], key: "o"): Workspace(2)"#,
+    ));
+    f.press_keys(&[keys::SUPER, keys::O]);
+
+    assert_eq!(f.data.state.workspaces.current, 2);
+}
+
+#[test]
+fn reload_applies_output_modes() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.reload_config(&TEST_CONFIG.replace(
+        "outputs: {",
+        r#"outputs: {
+        "HEADLESS-1": ((1280, 720), None),"#,
+    ));
+
+    let mode = f.output(0).current_mode().unwrap();
+    assert_eq!(mode.size, Size::from((1280, 720)));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1260, 700));
+    f.data.state.render_headless();
+}
+
+#[test]
+fn malformed_config_is_rejected() {
+    let mut f = Fixture::new();
+
+    f.reload_config("(workspaces: 3, gaps: (10, 10)");
+
+    assert_eq!(f.data.state.config.gaps, (5, 5));
+    let error = f.data.state.ipc_manager.config_error.clone().unwrap();
+    assert!(error.starts_with("Malformed config file"), "{}", error);
+}
+
+#[test]
+fn invalid_config_is_rejected() {
+    let mut f = Fixture::new();
+
+    f.reload_config(
+        &TEST_CONFIG
+            .replace("workspaces: 3", "workspaces: 0")
+            .replace("gaps: (5, 5)", "gaps: (10, 10)"),
+    );
+
+    assert_eq!(f.data.state.config.workspaces, 3);
+    assert_eq!(f.data.state.config.gaps, (5, 5));
+    let error = f.data.state.ipc_manager.config_error.clone().unwrap();
+    assert!(error.contains("workspaces"), "{}", error);
+}
+
+#[test]
+fn workspace_count_change_is_rejected() {
+    let mut f = Fixture::new();
+
+    f.reload_config(
+        &TEST_CONFIG
+            .replace("workspaces: 3", "workspaces: 4")
+            .replace("gaps: (5, 5)", "gaps: (10, 10)"),
+    );
+
+    assert_eq!(f.data.state.config.workspaces, 3);
+    assert_eq!(f.data.state.config.gaps, (5, 5));
+    let error = f.data.state.ipc_manager.config_error.clone().unwrap();
+    assert!(error.contains("requires a restart"), "{}", error);
+}
+
+#[test]
+fn invalid_xkb_config_is_rejected_before_anything_changes() {
+    let mut f = Fixture::new();
+
+    f.reload_config(
+        &TEST_CONFIG
+            .replace(r#"layout: "us""#, r#"layout: "no-such-layout""#)
+            .replace("gaps: (5, 5)", "gaps: (10, 10)"),
+    );
+
+    assert_eq!(f.data.state.config.xkb.layout, "us");
+    assert_eq!(f.data.state.config.gaps, (5, 5));
+    let error = f.data.state.ipc_manager.config_error.clone().unwrap();
+    assert!(error.contains("xkb"), "{}", error);
+}
+
+#[test]
+fn successful_reload_clears_error() {
+    let mut f = Fixture::new();
+
+    f.reload_config("");
+    assert!(f.data.state.ipc_manager.config_error.is_some());
+
+    f.reload_config(TEST_CONFIG);
+    assert!(f.data.state.ipc_manager.config_error.is_none());
+}
+
+#[test]
+fn default_config_is_valid() {
+    let config = Config::default_config();
+
+    config.validate().unwrap();
+    assert_eq!(config.workspaces, 9);
+    assert!(config.keybindings.values().any(|action| *action == Action::Quit));
+    assert!(config.keybindings.values().any(|action| matches!(action, Action::Spawn(_))));
+    for id in 0..9 {
+        assert!(config.keybindings.values().any(|action| *action == Action::Workspace(id)));
+    }
+}
+
+/// Line of the test config containing `needle`, counting from 1.
+fn line_of(needle: &str) -> usize {
+    TEST_CONFIG.lines().position(|line| line.contains(needle)).unwrap() + 1
+}
+
+#[test]
+fn unknown_action_is_located() {
+    let binding = r#"(modifiers: [Super], key: "m"): ToggleMaximize"#;
+    let err = load(&TEST_CONFIG.replace(binding, r#"(modifiers: [Super], key: "m"): Frobnicate"#))
+        .unwrap_err();
+
+    let ConfigError::Parse { ref error, ref line, .. } = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(error.position.line, line_of(binding));
+    assert!(line.as_deref().unwrap().contains("Frobnicate"));
+    assert!(err.to_string().contains("Frobnicate"), "{}", err);
+}
+
+#[test]
+fn unknown_keysym_is_reported() {
+    let err = load(&TEST_CONFIG.replace(r#"key: "m""#, r#"key: "NotAKey""#)).unwrap_err();
+
+    assert!(matches!(err, ConfigError::Parse { .. }), "{}", err);
+    assert!(err.to_string().contains("NotAKey"), "{}", err);
+}
+
+#[test]
+fn duplicate_keybinding_is_rejected() {
+    let err = load(&TEST_CONFIG.replace(
+        r#"(modifiers: [Super], key: "m"): ToggleMaximize"#,
+        r#"(modifiers: [Super], key: "q"): Quit"#,
+    ))
+    .unwrap_err();
+
+    assert!(matches!(err, ConfigError::Parse { .. }), "{}", err);
+    assert!(err.to_string().contains("Super+q is bound twice"), "{}", err);
+}
+
+#[test]
+fn workspace_out_of_range_is_rejected() {
+    let err = load(
+        &TEST_CONFIG
+            .replace("): Workspace(1)", "): Workspace(5)")
+            .replace("[2]", "[7]"),
+    )
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 2, "{}", err);
+    assert!(problems[0].contains("Super+2") && problems[0].contains("workspace 5"), "{}", err);
+    assert!(problems[1].contains("HEADLESS-2") && problems[1].contains("workspace 7"), "{}", err);
+}
+
+#[test]
+fn unknown_outputs_are_listed() {
+    let config = load(TEST_CONFIG).unwrap();
+
+    assert_eq!(config.unknown_outputs(&["HEADLESS-1".to_string()]), vec!["HEADLESS-2"]);
+    assert!(config
+        .unknown_outputs(&["HEADLESS-1".to_string(), "HEADLESS-2".to_string()])
+        .is_empty());
+}
+
+#[test]
+fn unknown_outputs_are_reported_on_reload() {
+    let mut f = Fixture::new();
+
+    f.reload_config(&TEST_CONFIG.replace("gaps: (5, 5)", "gaps: (10, 10)"));
+
+    // still applied, the output may be connected later on
+    assert_eq!(f.data.state.config.gaps, (10, 10));
+    assert!(f.data.state.ipc_manager.config_error.is_none());
+    assert_eq!(
+        f.data.state.ipc_manager.config_warnings,
+        vec!["output HEADLESS-2 is not connected".to_string()]
+    );
+}
+
+#[test]
+fn unmatched_outputs_are_listed() {
+    let config = load(&TEST_CONFIG.replace(
+        "    gaps: (5, 5),",
+        r#"    gaps: (5, 5),
+    input: { "touch": (map_to_output: Some("HEADLESS-3")), "tablet": (map_to_output: Some("HEADLESS-2")) },"#,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        config.unmatched_outputs(),
+        vec!["input touch: output HEADLESS-3 has no entry in `outputs`".to_string()]
+    );
+}
+
+#[test]
+fn unknown_mode_is_rejected() {
+    let err = load(&TEST_CONFIG.replace(r#"EnterMode("resize")"#, r#"EnterMode("resise")"#))
+        .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 1, "{}", err);
+    assert!(problems[0].contains("Super+r") && problems[0].contains("resise"), "{}", err);
+}
+
+#[test]
+fn sequence_shadowed_by_keybinding_is_rejected() {
+    let err = load(&TEST_CONFIG.replace(
+        r#"(modifiers: [Super], key: "w"), (modifiers: [], key: "h")]"#,
+        r#"(modifiers: [Super], key: "q"), (modifiers: [], key: "h")]"#,
+    ))
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(
+        problems,
+        &["keybinding Super+q, h: can never be completed, Super+q is bound on its own".to_string()]
+    );
+}
+
+#[test]
+fn release_and_repeat_binding_is_rejected() {
+    let err = load(&TEST_CONFIG.replace(
+        r#"key: "l", repeat: true)"#,
+        r#"key: "l", release: true, repeat: true)"#,
+    ))
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(
+        problems,
+        &["keybinding Super+Alt+l (release, repeat): a binding can't both trigger on release and repeat"
+            .to_string()]
+    );
+}
+
+#[test]
+fn keycode_bindings_are_parsed() {
+    let config = load(TEST_CONFIG).unwrap();
+    let pattern = config
+        .keybindings
+        .keys()
+        .find(|pattern| pattern.key == Key::Code(30))
+        .unwrap();
+    assert_eq!(pattern.to_string(), "Super+Code(30)");
+
+    let err = load(&TEST_CONFIG.replace("Code(30)", "Code(30, 31)")).unwrap_err();
+    assert!(matches!(err, ConfigError::Parse { .. }), "{}", err);
+}
+
+#[test]
+fn two_finger_swipe_is_rejected() {
+    let err = load(&TEST_CONFIG.replace(
+        "(fingers: 3, direction: Up): ToggleMaximize",
+        "(fingers: 2, direction: Up): ToggleMaximize",
+    ))
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 1, "{}", err);
+    assert!(problems[0].contains("2-finger swipe Up"), "{}", err);
+}
+
+#[test]
+fn layout_settings_are_validated() {
+    let err = load(
+        &TEST_CONFIG
+            .replace("workspace_layouts: { 1: MasterStack }", "workspace_layouts: { 5: Grid }")
+            .replace("layouts: [Bsp, MasterStack, Monocle]", "layouts: [], master_factor: 1.5, column_width: 0.0"),
+    )
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 4, "{}", err);
+    assert!(problems[0].contains("`layouts`"), "{}", err);
+    assert!(problems[1].contains("workspace 5"), "{}", err);
+    assert!(problems[2].contains("`master_factor`"), "{}", err);
+    assert!(problems[3].contains("`column_width`"), "{}", err);
+}
+
+#[test]
+fn preselect_ratio_is_validated() {
+    let err = load(&TEST_CONFIG.replace(
+        r#"(modifiers: [Super], key: "m"): ToggleMaximize"#,
+        r#"(modifiers: [Super], key: "m"): Preselect(Right, 95)"#,
+    ))
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 1, "{}", err);
+    assert!(problems[0].contains("Super+m"), "{}", err);
+    assert!(problems[0].contains("95"), "{}", err);
+}
+
+#[test]
+fn rotation_is_validated() {
+    let err = load(&TEST_CONFIG.replace(
+        r#"(modifiers: [Super], key: "m"): ToggleMaximize"#,
+        r#"(modifiers: [Super], key: "m"): RotateTree(45)"#,
+    ))
+    .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(problems.len(), 1, "{}", err);
+    assert!(problems[0].contains("Super+m"), "{}", err);
+    assert!(problems[0].contains("45"), "{}", err);
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use common::{keys, rect, Fixture, BTN_LEFT, BTN_RIGHT};
+use smithay::backend::input::{ButtonState, KeyState};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, ResizeEdge};
+
+#[test]
+fn toggle_floating_takes_window_out_of_layout() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1900, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(487, 10, 945, 1060));
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 945, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 10, 945, 1060));
+}
+
+#[test]
+fn floating_window_is_above_tiled_windows() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    // the floating window covers the tiled one in the middle of the output
+    f.move_pointer_to((960.0, 540.0));
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.move_pointer_to((100.0, 540.0));
+    assert!(f.is_focused(client, toplevels[0]));
+}
+
+#[test]
+fn fixed_size_window_floats_centered() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let tiled = f.spawn_toplevels(client, 1)[0];
+    let fixed = f.spawn_toplevel_with(client, |toplevel| {
+        toplevel.set_min_size(300, 200);
+        toplevel.set_max_size(300, 200);
+    });
+
+    assert_eq!(f.window_rect(client, tiled), rect(10, 10, 1900, 1060));
+    assert_eq!(f.window_rect(client, fixed), rect(810, 440, 300, 200));
+    assert_eq!(f.client(client).state.toplevels[fixed].size, Some((300, 200)));
+}
+
+#[test]
+fn dialog_floats_at_its_own_size() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let parent = f.spawn_toplevels(client, 1)[0];
+    let parent_toplevel = f.client(client).state.toplevels[parent].toplevel.clone();
+    let dialog = f.spawn_toplevel_with(client, |toplevel| {
+        toplevel.set_parent(Some(&parent_toplevel))
+    });
+
+    assert_eq!(f.window_rect(client, parent), rect(10, 10, 1900, 1060));
+    // the test client picks 100x100 when left to choose
+    assert_eq!(f.window_rect(client, dialog), rect(910, 490, 100, 100));
+}
+
+#[test]
+fn tiled_dialog_stays_tiled_when_remapped() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let parent = f.spawn_toplevels(client, 1)[0];
+    let parent_toplevel = f.client(client).state.toplevels[parent].toplevel.clone();
+    let dialog = f.spawn_toplevel_with(client, |toplevel| {
+        toplevel.set_parent(Some(&parent_toplevel))
+    });
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+    assert_eq!(f.window_rect(client, dialog), rect(965, 10, 945, 1060));
+
+    f.client(client).remap_toplevel(dialog);
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, dialog), rect(965, 10, 945, 1060));
+}
+
+#[test]
+fn super_left_drag_moves_floating_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    f.move_pointer_to((960.0, 540.0));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.button(BTN_LEFT, ButtonState::Pressed);
+    f.move_pointer_to((1060.0, 600.0));
+    f.button(BTN_LEFT, ButtonState::Released);
+    f.key(keys::SUPER, KeyState::Released);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(587, 70, 945, 1060));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1900, 1060));
+}
+
+#[test]
+fn dragging_tiled_window_makes_it_float() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.move_pointer_to((400.0, 540.0));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.button(BTN_LEFT, ButtonState::Pressed);
+    f.move_pointer_to((500.0, 540.0));
+    f.button(BTN_LEFT, ButtonState::Released);
+    f.key(keys::SUPER, KeyState::Released);
+
+    let window = f.window(client, toplevels[0]);
+    assert!(f.data.state.workspaces.current().is_floating(&window));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(110, 10, 945, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(10, 10, 1900, 1060));
+}
+
+#[test]
+fn super_right_drag_resizes_floating_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    // bottom right quadrant of the floating window at (487, 10, 945, 1060)
+    f.move_pointer_to((1300.0, 900.0));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.button(BTN_RIGHT, ButtonState::Pressed);
+    f.move_pointer_to((1200.0, 800.0));
+
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(toplevel.states.contains(&xdg_toplevel::State::Resizing));
+    assert_eq!(toplevel.size, Some((845, 960)));
+
+    f.button(BTN_RIGHT, ButtonState::Released);
+    f.key(keys::SUPER, KeyState::Released);
+
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(!toplevel.states.contains(&xdg_toplevel::State::Resizing));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(487, 10, 845, 960));
+}
+
+#[test]
+fn resize_state_is_set_before_any_motion() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    f.move_pointer_to((1300.0, 900.0));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.button(BTN_RIGHT, ButtonState::Pressed);
+    f.roundtrip();
+
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(toplevel.states.contains(&xdg_toplevel::State::Resizing));
+    assert_eq!(toplevel.size, Some((945, 1060)));
+
+    f.button(BTN_RIGHT, ButtonState::Released);
+    f.key(keys::SUPER, KeyState::Released);
+    f.roundtrip();
+
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(!toplevel.states.contains(&xdg_toplevel::State::Resizing));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(487, 10, 945, 1060));
+}
+
+#[test]
+fn client_move_request_starts_grab() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    f.move_pointer_to((960.0, 540.0));
+    f.button(BTN_LEFT, ButtonState::Pressed);
+    f.client(client).move_toplevel(toplevels[1]);
+    f.roundtrip();
+    f.move_pointer_to((860.0, 500.0));
+    f.button(BTN_LEFT, ButtonState::Released);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(387, -30, 945, 1060));
+}
+
+#[test]
+fn client_resize_request_moves_opposite_edge() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+
+    f.move_pointer_to((500.0, 540.0));
+    f.button(BTN_LEFT, ButtonState::Pressed);
+    f.client(client).resize_toplevel(toplevels[1], ResizeEdge::Left);
+    f.roundtrip();
+    f.move_pointer_to((600.0, 540.0));
+    f.button(BTN_LEFT, ButtonState::Released);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(587, 10, 845, 1060));
+}
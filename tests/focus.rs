@@ -0,0 +1,83 @@
+mod common;
+
+use common::{keys, Fixture};
+
+#[test]
+fn new_window_takes_focus() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+#[test]
+fn focus_follows_pointer() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.move_pointer_to((100.0, 100.0));
+    assert!(f.is_focused(client, toplevels[0]));
+
+    f.move_pointer_to((1500.0, 500.0));
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+#[test]
+fn close_binding_closes_window_under_pointer() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.move_pointer_to((100.0, 100.0));
+    f.press_keys(&[keys::SUPER, keys::Q]);
+
+    assert!(f.client(client).state.toplevels[toplevels[0]].closed);
+    assert!(!f.client(client).state.toplevels[toplevels[1]].closed);
+}
+
+#[test]
+fn focus_direction_picks_geometric_neighbour() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    assert!(f.is_focused(client, toplevels[2]));
+
+    f.press_keys(&[keys::SUPER, keys::K]);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER, keys::H]);
+    assert!(f.is_focused(client, toplevels[0]));
+
+    // nothing further left, focus stays
+    f.press_keys(&[keys::SUPER, keys::H]);
+    assert!(f.is_focused(client, toplevels[0]));
+}
+
+#[test]
+fn focus_next_and_prev_wrap_around() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+
+    f.press_keys(&[keys::SUPER, keys::TAB]);
+    assert!(f.is_focused(client, toplevels[0]));
+
+    f.press_keys(&[keys::SUPER, keys::SHIFT, keys::TAB]);
+    assert!(f.is_focused(client, toplevels[2]));
+}
+
+#[test]
+fn close_binding_targets_keyboard_focus() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.move_pointer_to((1500.0, 500.0));
+    f.press_keys(&[keys::SUPER, keys::H]);
+    f.press_keys(&[keys::SUPER, keys::Q]);
+
+    assert!(f.client(client).state.toplevels[toplevels[0]].closed);
+    assert!(!f.client(client).state.toplevels[toplevels[1]].closed);
+}
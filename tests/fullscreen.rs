@@ -0,0 +1,151 @@
+mod common;
+
+use common::{keys, rect, Fixture};
+use magma::utils::focus::FocusTarget;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::State;
+use wayland_protocols_wlr::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::Layer, zwlr_layer_surface_v1::Anchor,
+};
+
+#[test]
+fn toggle_fullscreen_covers_output_and_restores_slot() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.press_keys(&[keys::SUPER, keys::F]);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(0, 0, 1920, 1080));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 945, 1060));
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(toplevel.states.contains(&State::Fullscreen));
+    assert_eq!(toplevel.size, Some((1920, 1080)));
+
+    f.press_keys(&[keys::SUPER, keys::F]);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 10, 945, 1060));
+    let toplevel = &f.client(client).state.toplevels[toplevels[1]];
+    assert!(!toplevel.states.contains(&State::Fullscreen));
+    assert_eq!(toplevel.size, Some((945, 1060)));
+}
+
+#[test]
+fn maximize_fills_non_exclusive_zone() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let bar = f
+        .client(client)
+        .create_layer(Layer::Top, Anchor::Top | Anchor::Left | Anchor::Right, 30, (0, 30));
+    f.wait_until(|f| f.client(client).state.layers[bar].size.is_some());
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.press_keys(&[keys::SUPER, keys::M]);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(0, 30, 1920, 1050));
+    assert!(f.client(client).state.toplevels[toplevels[1]]
+        .states
+        .contains(&State::Maximized));
+
+    f.press_keys(&[keys::SUPER, keys::M]);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 40, 945, 1030));
+    assert!(!f.client(client).state.toplevels[toplevels[1]]
+        .states
+        .contains(&State::Maximized));
+}
+
+#[test]
+fn client_requests_change_state() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.client(client).state.toplevels[toplevels[0]]
+        .toplevel
+        .set_fullscreen(None);
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(0, 0, 1920, 1080));
+
+    f.client(client).state.toplevels[toplevels[0]]
+        .toplevel
+        .unset_fullscreen();
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 945, 1060));
+
+    f.client(client).state.toplevels[toplevels[0]]
+        .toplevel
+        .set_maximized();
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(0, 0, 1920, 1080));
+    assert!(f.client(client).state.toplevels[toplevels[0]]
+        .states
+        .contains(&State::Maximized));
+
+    f.client(client).state.toplevels[toplevels[0]]
+        .toplevel
+        .unset_maximized();
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 945, 1060));
+}
+
+#[test]
+fn fullscreen_window_is_above_top_layers() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let bar = f
+        .client(client)
+        .create_layer(Layer::Top, Anchor::Top | Anchor::Left | Anchor::Right, 30, (0, 30));
+    f.wait_until(|f| f.client(client).state.layers[bar].size.is_some());
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.move_pointer_to((960.0, 10.0));
+    assert!(matches!(
+        f.data.state.surface_under(),
+        Some((FocusTarget::LayerSurface(_), _))
+    ));
+
+    f.press_keys(&[keys::SUPER, keys::F]);
+
+    let window = f.window(client, toplevels[0]);
+    assert_eq!(
+        f.data.state.surface_under().map(|(target, _)| target),
+        Some(FocusTarget::Window(window))
+    );
+}
+
+#[test]
+fn floating_window_gets_geometry_back() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(487, 10, 945, 1060));
+
+    f.press_keys(&[keys::SUPER, keys::M]);
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(0, 0, 1920, 1080));
+
+    f.press_keys(&[keys::SUPER, keys::M]);
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(487, 10, 945, 1060));
+    assert_eq!(f.client(client).state.toplevels[toplevels[1]].size, Some((945, 1060)));
+}
+
+#[test]
+fn fullscreen_requested_before_first_commit_is_applied() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let tiled = f.spawn_toplevels(client, 1)[0];
+    let fullscreen = f.spawn_toplevel_with(client, |toplevel| toplevel.set_fullscreen(None));
+
+    assert_eq!(f.window_rect(client, fullscreen), rect(0, 0, 1920, 1080));
+    let toplevel = &f.client(client).state.toplevels[fullscreen];
+    assert!(toplevel.states.contains(&State::Fullscreen));
+    assert_eq!(toplevel.size, Some((1920, 1080)));
+
+    // leaving fullscreen puts it in its place in the layout
+    f.client(client).state.toplevels[fullscreen]
+        .toplevel
+        .unset_fullscreen();
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, fullscreen), rect(965, 10, 945, 1060));
+    assert_eq!(f.window_rect(client, tiled), rect(10, 10, 945, 1060));
+}
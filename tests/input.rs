@@ -0,0 +1,122 @@
+mod common;
+
+use common::{keys, load, Fixture, TEST_CONFIG};
+use magma::config::{ConfigError, InputConfig};
+
+/// The test config with `input` set to `entries`.
+fn with_input(entries: &str) -> String {
+    TEST_CONFIG.replace("    gaps: (5, 5),", &format!("    gaps: (5, 5),\n    input: {{ {} }},", entries))
+}
+
+#[test]
+fn keyboard_repeat_defaults() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.roundtrip();
+
+    assert_eq!(f.data.state.key_repeat_info, (200, 25));
+    assert_eq!(f.client(client).state.repeat_info, Some((200, 25)));
+}
+
+#[test]
+fn keyboard_repeat_is_configured_by_kind() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.reload_config(&with_input(r#""keyboard": (repeat_delay: Some(300), repeat_rate: Some(40))"#));
+
+    f.press_keys(&[keys::L]);
+    assert_eq!(f.data.state.key_repeat_info, (300, 40));
+    assert_eq!(f.client(client).state.repeat_info, Some((300, 40)));
+}
+
+#[test]
+fn device_name_wins_over_kind() {
+    let mut f = Fixture::new();
+    f.press_keys(&[keys::L]);
+    f.reload_config(&with_input(
+        r#""keyboard": (repeat_delay: Some(300), repeat_rate: Some(40)), "Magma test device": (repeat_rate: Some(10))"#,
+    ));
+
+    // applied to the active keyboard right away
+    assert_eq!(f.data.state.active_keyboard.as_deref(), Some("Magma test device"));
+    assert_eq!(f.data.state.key_repeat_info, (300, 10));
+}
+
+#[test]
+fn input_config_merges_entries() {
+    let config = load(&with_input(
+        r#""pointer": (accel_profile: Some(Flat), accel_speed: Some(0.5)), "touchpad": (tap: Some(true), accel_speed: Some(0.2))"#,
+    ))
+    .unwrap();
+
+    let touchpad = config.input_config("SynPS/2 Synaptics TouchPad", &["touchpad", "pointer"]);
+    assert_eq!(touchpad.tap, Some(true));
+    assert_eq!(touchpad.accel_speed, Some(0.2));
+    assert!(touchpad.accel_profile.is_some());
+
+    let mouse = config.input_config("Logitech USB Mouse", &["pointer"]);
+    assert_eq!(mouse.tap, None);
+    assert_eq!(mouse.accel_speed, Some(0.5));
+
+    assert_eq!(config.input_config("Yubikey", &["keyboard"]), InputConfig::default());
+}
+
+#[test]
+fn input_kind_config_skips_device_entries() {
+    let config = load(&with_input(
+        r#""keyboard": (repeat_rate: Some(40)), "": (repeat_delay: Some(500))"#,
+    ))
+    .unwrap();
+
+    let keyboard = config.input_kind_config(&["keyboard"]);
+    assert_eq!(keyboard.repeat_rate, Some(40));
+    assert_eq!(keyboard.repeat_delay, None);
+}
+
+#[test]
+fn invalid_input_config_is_rejected() {
+    let err = load(&with_input(r#""pointer": (accel_speed: Some(2.0)), "keyboard": (repeat_delay: Some(0))"#))
+        .unwrap_err();
+
+    let ConfigError::Invalid(ref problems) = err else {
+        panic!("{}", err);
+    };
+    assert_eq!(
+        problems,
+        &[
+            "input keyboard: `repeat_delay` has to be positive, got 0".to_string(),
+            "input pointer: `accel_speed` has to be between -1 and 1, got 2".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn touch_reaches_surface_under_finger() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.touch_down(0, (0.25, 0.5));
+    assert!(f.is_focused(client, toplevels[0]));
+    assert_eq!(f.client(client).state.touch_points, vec![(0, Some((470.0, 530.0)))]);
+
+    f.touch_motion(0, (0.3, 0.5));
+    assert_eq!(f.client(client).state.touch_points, vec![(0, Some((566.0, 530.0)))]);
+
+    f.touch_up(0);
+    assert_eq!(f.client(client).state.touch_points, vec![(0, None)]);
+}
+
+#[test]
+fn touch_maps_to_configured_output() {
+    let mut f = Fixture::with_outputs(&[(1920, 1080), (1920, 1080)]);
+    f.reload_config(&with_input(r#""touch": (map_to_output: Some("HEADLESS-2"))"#));
+
+    f.touch_down(0, (0.5, 0.5));
+    assert_eq!(f.data.state.workspaces.current_output(), Some(&f.output(1)));
+
+    f.reload_config(TEST_CONFIG);
+    f.touch_down(1, (0.5, 0.5));
+    assert_eq!(f.data.state.workspaces.current_output(), Some(&f.output(0)));
+}
@@ -0,0 +1,299 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::{keys, rect, Fixture, TEST_CONFIG};
+use wayland_client::protocol::wl_keyboard::KeyState;
+
+#[test]
+fn mode_replaces_keybindings() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::H]);
+
+    f.press_keys(&[keys::SUPER, keys::R]);
+    assert_eq!(f.data.state.mode.as_deref(), Some("resize"));
+
+    f.press_keys(&[keys::L]);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1136, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(1156, 10, 754, 1060));
+    assert!(!f
+        .client(client)
+        .state
+        .keys
+        .contains(&(keys::L, KeyState::Pressed)));
+
+    f.press_keys(&[keys::ESC]);
+    assert_eq!(f.data.state.mode, None);
+
+    // back in the default mode, a plain l goes to the client
+    f.press_keys(&[keys::L]);
+    assert!(f
+        .client(client)
+        .state
+        .keys
+        .contains(&(keys::L, KeyState::Pressed)));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1136, 1060));
+}
+
+#[test]
+fn passthrough_mode_forwards_default_bindings() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.press_keys(&[keys::SUPER, keys::P]);
+    f.press_keys(&[keys::SUPER, keys::Q]);
+
+    assert!(!f.client(client).state.toplevels[toplevels[0]].closed);
+    assert!(f
+        .client(client)
+        .state
+        .keys
+        .contains(&(keys::Q, KeyState::Pressed)));
+
+    f.press_keys(&[keys::SUPER, keys::ESC]);
+    assert_eq!(f.data.state.mode, None);
+    f.press_keys(&[keys::SUPER, keys::Q]);
+    assert!(f.client(client).state.toplevels[toplevels[0]].closed);
+}
+
+#[test]
+fn active_mode_is_sent_over_ipc() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+
+    f.client(client).subscribe_keybindings();
+    f.roundtrip();
+    assert_eq!(f.client(client).state.active_mode.as_deref(), Some("default"));
+
+    f.press_keys(&[keys::SUPER, keys::R]);
+    assert_eq!(f.client(client).state.active_mode.as_deref(), Some("resize"));
+
+    f.press_keys(&[keys::ESC]);
+    assert_eq!(f.client(client).state.active_mode.as_deref(), Some("default"));
+}
+
+#[test]
+fn reload_leaves_removed_mode() {
+    let mut f = Fixture::new();
+    f.press_keys(&[keys::SUPER, keys::R]);
+
+    let start = TEST_CONFIG.find("        \"resize\": (").unwrap();
+    let end = start + TEST_CONFIG[start..].find("        }),\n").unwrap() + "        }),\n".len();
+    let config = TEST_CONFIG[..start].to_string() + &TEST_CONFIG[end..];
+    f.reload_config(&config.replace(
+        r#"(modifiers: [Super], key: "r"): EnterMode("resize"),"#,
+        "",
+    ));
+
+    assert!(f.data.state.ipc_manager.config_error.is_none());
+    assert_eq!(f.data.state.mode, None);
+}
+
+#[test]
+fn sequence_triggers_action() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER, keys::W]);
+    f.press_keys(&[keys::H]);
+    assert!(f.is_focused(client, toplevels[0]));
+    assert!(f.data.state.pending_keys.is_empty());
+
+    // neither the sequence nor the releases of its keys reach the client
+    let keys = &f.client(client).state.keys;
+    assert!(!keys.iter().any(|(key, _)| *key == keys::W || *key == keys::H));
+}
+
+#[test]
+fn three_key_sequence() {
+    let mut f = Fixture::new();
+
+    f.press_keys(&[keys::SUPER, keys::W]);
+    f.press_keys(&[keys::G]);
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(0));
+    f.press_keys(&[keys::KEY_2]);
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(1));
+}
+
+#[test]
+fn pending_keys_are_sent_over_ipc() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.client(client).subscribe_keybindings();
+    f.roundtrip();
+    assert_eq!(f.client(client).state.pending_keys.as_deref(), Some(""));
+
+    f.press_keys(&[keys::SUPER, keys::W]);
+    assert_eq!(f.client(client).state.pending_keys.as_deref(), Some("Super+w"));
+    f.press_keys(&[keys::G]);
+    assert_eq!(f.client(client).state.pending_keys.as_deref(), Some("Super+w, g"));
+    f.press_keys(&[keys::KEY_2]);
+    assert_eq!(f.client(client).state.pending_keys.as_deref(), Some(""));
+}
+
+#[test]
+fn unmatched_key_cancels_sequence() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.press_keys(&[keys::SUPER, keys::W]);
+    f.press_keys(&[keys::X]);
+    assert!(f.data.state.pending_keys.is_empty());
+    assert!(!f
+        .client(client)
+        .state
+        .keys
+        .contains(&(keys::X, KeyState::Pressed)));
+
+    // the sequence has to start over
+    f.press_keys(&[keys::H]);
+    assert!(f.is_focused(client, toplevels[1]));
+    assert!(f
+        .client(client)
+        .state
+        .keys
+        .contains(&(keys::H, KeyState::Pressed)));
+}
+
+#[test]
+fn sequence_times_out() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.press_keys(&[keys::SUPER, keys::W]);
+    assert_eq!(f.data.state.pending_keys.len(), 1);
+
+    thread::sleep(Duration::from_millis(250));
+    f.dispatch();
+    assert!(f.data.state.pending_keys.is_empty());
+
+    f.press_keys(&[keys::H]);
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+#[test]
+fn release_binding_triggers_on_tap() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER]);
+    assert!(f.is_focused(client, toplevels[0]));
+
+    // Super used for another binding is no tap
+    f.press_keys(&[keys::SUPER, keys::L]);
+    assert!(f.is_focused(client, toplevels[1]));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.click(common::BTN_LEFT);
+    f.key(keys::SUPER, KeyState::Released);
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+#[test]
+fn repeat_binding_repeats_while_held() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.press_keys(&[keys::SUPER, keys::H]);
+
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.key(keys::ALT, KeyState::Pressed);
+    f.key(keys::L, KeyState::Pressed);
+    assert_eq!(f.window_rect(client, toplevels[0]).size.w, 955);
+
+    thread::sleep(Duration::from_millis(300));
+    f.dispatch();
+    let width = f.window_rect(client, toplevels[0]).size.w;
+    assert!(width > 955, "{}", width);
+
+    f.key(keys::L, KeyState::Released);
+    thread::sleep(Duration::from_millis(300));
+    f.dispatch();
+    assert_eq!(f.window_rect(client, toplevels[0]).size.w, width);
+}
+
+#[test]
+fn button_binding_is_not_forwarded() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+    let window = f.window(client, toplevels[0]);
+    f.move_pointer_to((500.0, 500.0));
+
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.click(common::BTN_MIDDLE);
+    f.key(keys::SUPER, KeyState::Released);
+
+    assert!(f.data.state.workspaces.current().is_floating(&window));
+    assert_eq!(f.client(client).state.button_serial, None);
+}
+
+#[test]
+fn scroll_binding_switches_workspace() {
+    let mut f = Fixture::new();
+
+    f.scroll(1.0);
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(0));
+
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.scroll(1.0);
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(1));
+    f.scroll(-1.0);
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(0));
+    f.key(keys::SUPER, KeyState::Released);
+}
+
+#[test]
+fn keycode_binding() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    assert!(f.is_focused(client, toplevels[2]));
+
+    f.press_keys(&[keys::SUPER, keys::U]);
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+/// The test config with a second, cyrillic layout toggled by Caps Lock.
+fn multilingual_config(bind_base_layout: bool) -> String {
+    TEST_CONFIG
+        .replace("layout: \"us\",", "layout: \"us,ru\",")
+        .replace("options: None,", "options: Some(\"grp:caps_toggle\"),")
+        .replace(
+            "workspaces: 3,",
+            &format!("workspaces: 3,\n    bind_base_layout: {},", bind_base_layout),
+        )
+}
+
+#[test]
+fn active_layout_is_matched_by_default() {
+    let mut f = Fixture::new();
+    f.reload_config(&multilingual_config(false));
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.press_keys(&[keys::CAPS]);
+    f.press_keys(&[keys::SUPER, keys::Q]);
+    assert!(!f.client(client).state.toplevels[toplevels[0]].closed);
+}
+
+#[test]
+fn base_layout_bindings_work_with_other_layouts() {
+    let mut f = Fixture::new();
+    f.reload_config(&multilingual_config(true));
+    assert!(f.data.state.ipc_manager.config_error.is_none());
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.press_keys(&[keys::CAPS]);
+    f.press_keys(&[keys::SUPER, keys::Q]);
+    assert!(f.client(client).state.toplevels[toplevels[0]].closed);
+}
@@ -0,0 +1,660 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{keys, rect, Fixture, BTN_RIGHT, TEST_CONFIG};
+use magma::{
+    config::{Action, Direction, Flip, LayoutKind},
+    utils::{
+        group::{Group, GroupKind, MIN_WINDOW_HEIGHT},
+        workspaces::MagmaWindow,
+    },
+};
+use smithay::backend::input::{ButtonState, KeyState};
+use wayland_protocols_wlr::layer_shell::v1::client::{
+    zwlr_layer_shell_v1::Layer, zwlr_layer_surface_v1::Anchor,
+};
+
+#[test]
+fn single_window_fills_output() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1900, 1060));
+    assert_eq!(f.client(client).state.toplevels[0].size, Some((1900, 1060)));
+}
+
+#[test]
+fn second_window_splits_horizontally() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 2);
+
+    assert_eq!(
+        f.window_rects(),
+        vec![rect(10, 10, 945, 1060), rect(965, 10, 945, 1060)]
+    );
+}
+
+#[test]
+fn third_window_splits_vertically() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 945, 525),
+            rect(965, 545, 945, 525),
+        ]
+    );
+}
+
+#[test]
+fn removing_window_restores_layout() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.destroy_toplevel(client, toplevels[0]);
+
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(10, 10, 1900, 1060));
+}
+
+#[test]
+fn exclusive_layer_shrinks_tiling_area() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let bar = f
+        .client(client)
+        .create_layer(Layer::Top, Anchor::Top | Anchor::Left | Anchor::Right, 30, (0, 30));
+    f.wait_until(|f| f.client(client).state.layers[bar].size.is_some());
+
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 40, 1900, 1030));
+}
+
+#[test]
+fn swap_window_exchanges_rectangles() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.press_keys(&[keys::SUPER, keys::SHIFT, keys::H]);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(965, 10, 945, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(10, 10, 945, 1060));
+    assert!(f.is_focused(client, toplevels[1]));
+}
+
+#[test]
+fn move_window_splits_neighbour() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    assert!(f.is_focused(client, toplevels[2]));
+
+    f.press_keys(&[keys::SUPER, keys::CTRL, keys::H]);
+
+    assert_eq!(f.window_rect(client, toplevels[2]), rect(10, 10, 467, 1060));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(487, 10, 468, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 10, 945, 1060));
+}
+
+#[test]
+fn resize_window_moves_split_boundary() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.press_keys(&[keys::SUPER, keys::H]);
+    f.press_keys(&[keys::SUPER, keys::RIGHT]);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1136, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(1156, 10, 754, 1060));
+
+    // the left window has no boundary on its left side
+    f.press_keys(&[keys::SUPER, keys::LEFT]);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1136, 1060));
+}
+
+#[test]
+fn super_right_drag_resizes_split() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.move_pointer_to((1000.0, 540.0));
+    f.key(keys::SUPER, KeyState::Pressed);
+    f.button(BTN_RIGHT, ButtonState::Pressed);
+    f.move_pointer_to((809.0, 540.0));
+    f.button(BTN_RIGHT, ButtonState::Released);
+    f.key(keys::SUPER, KeyState::Released);
+
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 754, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(774, 10, 1136, 1060));
+}
+
+#[test]
+fn master_stack_layout() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::MasterStack));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 1040, 1060),
+            rect(1060, 10, 850, 525),
+            rect(1060, 545, 850, 525),
+        ]
+    );
+
+    f.data.state.handle_action(Action::ChangeMasterCount(1));
+    f.data.state.handle_action(Action::ChangeMasterFactor(-5));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 525),
+            rect(10, 545, 945, 525),
+            rect(965, 10, 945, 1060),
+        ]
+    );
+}
+
+#[test]
+fn reload_keeps_runtime_layout_with_new_master_settings() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 2);
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::MasterStack));
+
+    f.reload_config(&TEST_CONFIG.replace("    gaps: (5, 5),", "    gaps: (5, 5),\n    master_factor: 0.5,"));
+
+    let kind = f.data.state.workspaces.current().layout().kind();
+    assert_eq!(kind, LayoutKind::MasterStack);
+    assert_eq!(f.window_rects(), vec![rect(10, 10, 945, 1060), rect(965, 10, 945, 1060)]);
+}
+
+#[test]
+fn columns_and_grid_layouts() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Columns));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 626, 1060),
+            rect(646, 10, 627, 1060),
+            rect(1283, 10, 627, 1060),
+        ]
+    );
+
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Grid));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 525),
+            rect(965, 10, 945, 525),
+            rect(10, 545, 1900, 525),
+        ]
+    );
+
+    // the BSP tree was kept up to date meanwhile
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Bsp));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 945, 525),
+            rect(965, 545, 945, 525),
+        ]
+    );
+}
+
+#[test]
+fn monocle_shows_focused_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Monocle));
+    f.roundtrip();
+    assert_eq!(f.window_rects(), vec![rect(10, 10, 1900, 1060); 2]);
+    let shown = |f: &Fixture| {
+        f.data
+            .state
+            .workspaces
+            .current()
+            .window_under((500.0, 500.0))
+            .map(|(window, _)| window.clone())
+    };
+    assert_eq!(shown(&f), Some(f.window(client, toplevels[1])));
+
+    f.press_keys(&[keys::SUPER, keys::TAB]);
+    assert!(f.is_focused(client, toplevels[0]));
+    assert_eq!(shown(&f), Some(f.window(client, toplevels[0])));
+}
+
+#[test]
+fn cycle_layout_and_workspace_layouts() {
+    let mut f = Fixture::new();
+    let layout = |f: &Fixture| f.data.state.workspaces.current().layout().kind();
+    assert_eq!(layout(&f), LayoutKind::Bsp);
+
+    f.press_keys(&[keys::SUPER, keys::T]);
+    assert_eq!(layout(&f), LayoutKind::MasterStack);
+    f.press_keys(&[keys::SUPER, keys::T]);
+    f.press_keys(&[keys::SUPER, keys::T]);
+    assert_eq!(layout(&f), LayoutKind::Bsp);
+
+    f.press_keys(&[keys::SUPER, keys::KEY_2]);
+    assert_eq!(layout(&f), LayoutKind::MasterStack);
+    let client = f.add_client();
+    f.spawn_toplevels(client, 2);
+    assert_eq!(
+        f.window_rects(),
+        vec![rect(10, 10, 1040, 1060), rect(1060, 10, 850, 1060)]
+    );
+}
+
+#[test]
+fn scrolling_layout_follows_focus() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Scrolling));
+    f.roundtrip();
+    // the focused last column is scrolled into view
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(-945, 10, 945, 1060),
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 945, 1060),
+        ]
+    );
+
+    f.press_keys(&[keys::SUPER, keys::H]);
+    assert!(f.is_focused(client, toplevels[1]));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(10, 10, 945, 1060));
+
+    f.press_keys(&[keys::SUPER, keys::H]);
+    assert!(f.is_focused(client, toplevels[0]));
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 945, 1060),
+            rect(1920, 10, 945, 1060),
+        ]
+    );
+}
+
+#[test]
+fn scrolling_layout_moves_and_resizes_columns() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Scrolling));
+    f.press_keys(&[keys::SUPER, keys::H]);
+    f.press_keys(&[keys::SUPER, keys::H]);
+
+    f.data.state.handle_action(Action::ChangeColumnWidth(50));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 1900, 1060),
+            rect(1920, 10, 945, 1060),
+            rect(2875, 10, 945, 1060),
+        ]
+    );
+
+    f.press_keys(&[keys::SUPER, keys::CTRL, keys::L]);
+    assert!(f.is_focused(client, toplevels[0]));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(-945, 10, 945, 1060));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1900, 1060));
+    assert_eq!(f.window_rect(client, toplevels[2]), rect(1920, 10, 945, 1060));
+}
+
+#[test]
+fn reload_keeps_resized_columns() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Scrolling));
+    f.press_keys(&[keys::SUPER, keys::H]);
+    f.data.state.handle_action(Action::ChangeColumnWidth(-20));
+
+    f.reload_config(&TEST_CONFIG.replace("    gaps: (5, 5),", "    gaps: (5, 5),\n    column_width: 0.4,"));
+
+    let kind = f.data.state.workspaces.current().layout().kind();
+    assert_eq!(kind, LayoutKind::Scrolling);
+    // the resized column keeps its width, the other one takes the new default
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 563, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(583, 10, 754, 1060));
+}
+
+#[test]
+fn preselection_places_next_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    f.press_keys(&[keys::SUPER, keys::H]);
+    assert!(f.is_focused(client, toplevels[0]));
+
+    f.data.state.handle_action(Action::Preselect(Direction::Down, 50));
+    assert_eq!(
+        f.data.state.workspaces.current().preselection_area(),
+        Some(rect(10, 540, 945, 530))
+    );
+
+    let new = f.spawn_toplevels(client, 1);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 945, 525));
+    assert_eq!(f.window_rect(client, new[0]), rect(10, 545, 945, 525));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 10, 945, 525));
+    assert!(f.data.state.workspaces.current().preselection().is_none());
+}
+
+#[test]
+fn preselection_ratio_goes_to_new_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+
+    f.data.state.handle_action(Action::Preselect(Direction::Left, 30));
+    assert_eq!(
+        f.data.state.workspaces.current().preselection_area(),
+        Some(rect(965, 10, 283, 1060))
+    );
+
+    let new = f.spawn_toplevels(client, 1);
+    assert_eq!(f.window_rect(client, new[0]), rect(965, 10, 276, 1060));
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(1251, 10, 659, 1060));
+}
+
+#[test]
+fn preselection_is_cancelled() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 2);
+    let preselection = |f: &Fixture| f.data.state.workspaces.current().preselection().is_some();
+
+    f.data.state.handle_action(Action::Preselect(Direction::Up, 50));
+    assert!(preselection(&f));
+    f.data.state.handle_action(Action::CancelPreselect);
+    assert!(!preselection(&f));
+
+    // floating the preselected window drops the split
+    f.data.state.handle_action(Action::Preselect(Direction::Up, 50));
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+    assert!(!preselection(&f));
+
+    // other layouts have no splits to preselect
+    f.data.state.handle_action(Action::SetLayout(LayoutKind::Monocle));
+    f.data.state.handle_action(Action::Preselect(Direction::Up, 50));
+    assert!(!preselection(&f));
+}
+
+#[test]
+fn balance_gives_lopsided_splits_equal_shares() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 11);
+
+    f.data.state.handle_action(Action::BalanceTree);
+    f.roundtrip();
+    // the first window is alone next to ten others, 1910 / 11 wide
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 163, 1060));
+}
+
+#[test]
+fn balance_and_equalize_tree() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::BalanceTree);
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 626, 1060),
+            rect(646, 10, 1264, 525),
+            rect(646, 545, 1264, 525),
+        ]
+    );
+
+    // ratios are mirrored along with the tree
+    f.data.state.handle_action(Action::RotateTree(180));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(1283, 10, 627, 1060),
+            rect(10, 545, 1263, 525),
+            rect(10, 10, 1263, 525),
+        ]
+    );
+
+    f.data.state.handle_action(Action::EqualizeSplits);
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(965, 10, 945, 1060),
+            rect(10, 545, 945, 525),
+            rect(10, 10, 945, 525),
+        ]
+    );
+}
+
+#[test]
+fn rotate_tree() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::RotateTree(90));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 1900, 525),
+            rect(965, 545, 945, 525),
+            rect(10, 545, 945, 525),
+        ]
+    );
+
+    f.data.state.handle_action(Action::RotateTree(270));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 945, 525),
+            rect(965, 545, 945, 525),
+        ]
+    );
+
+    f.data.state.handle_action(Action::RotateTree(270));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 545, 1900, 525),
+            rect(10, 10, 945, 525),
+            rect(965, 10, 945, 525),
+        ]
+    );
+}
+
+#[test]
+fn flip_tree() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.spawn_toplevels(client, 3);
+
+    f.data.state.handle_action(Action::FlipTree(Flip::Horizontal));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(965, 10, 945, 1060),
+            rect(10, 10, 945, 525),
+            rect(10, 545, 945, 525),
+        ]
+    );
+
+    f.data.state.handle_action(Action::FlipTree(Flip::Vertical));
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(965, 10, 945, 1060),
+            rect(10, 545, 945, 525),
+            rect(10, 10, 945, 525),
+        ]
+    );
+}
+
+#[test]
+fn group_windows_as_tabs() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    let hidden = |f: &Fixture| -> Vec<bool> {
+        f.data
+            .state
+            .workspaces
+            .current()
+            .magmawindows()
+            .map(|w| w.hidden)
+            .collect()
+    };
+    let shown = |f: &Fixture| {
+        f.data
+            .state
+            .workspaces
+            .current()
+            .window_under((1400.0, 500.0))
+            .map(|(window, _)| window.clone())
+    };
+
+    f.data.state.handle_action(Action::GroupWith(Direction::Up));
+    f.roundtrip();
+    // the windows share the leaf below a single row of tabs
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 30, 945, 1040),
+            rect(965, 30, 945, 1040),
+        ]
+    );
+    assert_eq!(hidden(&f), vec![false, true, false]);
+    assert_eq!(shown(&f), Some(f.window(client, toplevels[2])));
+
+    f.data.state.handle_action(Action::PrevTab);
+    assert!(f.is_focused(client, toplevels[1]));
+    assert_eq!(hidden(&f), vec![false, false, true]);
+    assert_eq!(shown(&f), Some(f.window(client, toplevels[1])));
+
+    // focusing a window of the group from outside shows it
+    f.press_keys(&[keys::SUPER, keys::H]);
+    f.press_keys(&[keys::SUPER, keys::L]);
+    assert!(f.is_focused(client, toplevels[1]));
+
+    f.data.state.handle_action(Action::ToggleGroupKind);
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(965, 50, 945, 1020));
+}
+
+#[test]
+fn ungroup_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    f.data.state.handle_action(Action::GroupWith(Direction::Up));
+
+    f.data.state.handle_action(Action::Ungroup);
+    f.roundtrip();
+    assert_eq!(
+        f.window_rects(),
+        vec![
+            rect(10, 10, 945, 1060),
+            rect(965, 10, 467, 1060),
+            rect(1442, 10, 468, 1060),
+        ]
+    );
+    assert!(f
+        .data
+        .state
+        .workspaces
+        .current()
+        .magmawindows()
+        .all(|w| !w.hidden));
+    assert!(f.is_focused(client, toplevels[2]));
+
+    // there is no group left to leave
+    f.data.state.handle_action(Action::Ungroup);
+    f.roundtrip();
+    assert_eq!(f.window_rect(client, toplevels[2]), rect(1442, 10, 468, 1060));
+}
+
+#[test]
+fn floating_a_tab_dissolves_the_group() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 2);
+    f.data.state.handle_action(Action::GroupWith(Direction::Left));
+    f.roundtrip();
+    assert_eq!(f.window_rects(), vec![rect(10, 30, 1900, 1040); 2]);
+
+    f.press_keys(&[keys::SUPER, keys::SPACE]);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(10, 10, 1900, 1060));
+}
+
+#[test]
+fn stacked_tab_bar_leaves_room_for_the_window() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 3);
+    let windows = toplevels
+        .iter()
+        .map(|&toplevel| {
+            let window = f.window(client, toplevel);
+            Rc::new(RefCell::new(MagmaWindow::new(window, rect(0, 0, 0, 0))))
+        })
+        .collect();
+    let mut group = Group::new(windows, 0);
+    group.kind = GroupKind::Stacked;
+
+    group.arrange(rect(0, 0, 500, 1000));
+    assert_eq!(group.bar, rect(0, 0, 500, 60));
+    assert_eq!(group.windows[0].borrow().rec, rect(0, 60, 500, 940));
+
+    // the rows shrink instead of pushing the window below its minimum height
+    group.arrange(rect(0, 0, 500, 150));
+    assert_eq!(group.bar, rect(0, 0, 500, 50));
+    assert_eq!(group.windows[0].borrow().rec, rect(0, 50, 500, MIN_WINDOW_HEIGHT));
+    let rows = group.tab_areas();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows.iter().map(|row| row.size.h).sum::<i32>(), 50);
+    assert_eq!(rows[2].loc.y + rows[2].size.h, 50);
+}
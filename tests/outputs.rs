@@ -0,0 +1,101 @@
+mod common;
+
+use common::{keys, rect, Fixture};
+use smithay::utils::Point;
+
+fn two_outputs() -> Fixture {
+    Fixture::with_outputs(&[(1920, 1080), (1920, 1080)])
+}
+
+#[test]
+fn outputs_are_placed_side_by_side() {
+    let f = two_outputs();
+    let workspaces = &f.data.state.workspaces;
+
+    assert_eq!(f.output(0).current_location(), Point::from((0, 0)));
+    assert_eq!(f.output(1).current_location(), Point::from((1920, 0)));
+    assert_eq!(workspaces.active_id_on(&f.output(0)), Some(0));
+    // workspace 2 is bound to HEADLESS-2 by the test configuration
+    assert_eq!(workspaces.active_id_on(&f.output(1)), Some(2));
+    assert_eq!(workspaces.current, 0);
+}
+
+#[test]
+fn pointer_moves_focus_across_outputs() {
+    let mut f = two_outputs();
+    let client = f.add_client();
+
+    f.move_pointer_to((2880.0, 540.0));
+    assert_eq!(f.data.state.workspaces.current, 2);
+
+    let toplevels = f.spawn_toplevels(client, 1);
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(1930, 10, 1900, 1060));
+
+    // both outputs render their own workspace
+    f.data.state.render_headless();
+}
+
+#[test]
+fn pointer_is_clamped_to_output_layout() {
+    let mut f = two_outputs();
+
+    f.move_pointer_to((5000.0, -100.0));
+
+    assert_eq!(f.data.state.pointer_location, Point::from((3840.0, 0.0)));
+}
+
+#[test]
+fn focus_output_moves_focus_and_pointer() {
+    let mut f = two_outputs();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.press_keys(&[keys::SUPER, keys::O]);
+    assert_eq!(f.data.state.workspaces.current, 2);
+    assert_eq!(f.data.state.pointer_location, Point::from((2880.0, 540.0)));
+
+    f.press_keys(&[keys::SUPER, keys::I]);
+    assert_eq!(f.data.state.workspaces.current, 0);
+    assert!(f.is_focused(client, toplevels[0]));
+}
+
+#[test]
+fn move_workspace_to_output() {
+    let mut f = two_outputs();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.press_keys(&[keys::SUPER, keys::SHIFT, keys::O]);
+
+    let workspaces = &f.data.state.workspaces;
+    assert_eq!(workspaces.current, 0);
+    assert_eq!(workspaces.active_id_on(&f.output(1)), Some(0));
+    // the hidden workspace laid out on the first output takes its place
+    assert_eq!(workspaces.active_id_on(&f.output(0)), Some(1));
+    assert_eq!(f.window_rect(client, toplevels[0]), rect(1930, 10, 1900, 1060));
+}
+
+#[test]
+fn hidden_workspace_appears_on_focused_output() {
+    let mut f = two_outputs();
+
+    f.move_pointer_to((2880.0, 540.0));
+    f.press_keys(&[keys::SUPER, keys::KEY_2]);
+
+    let workspaces = &f.data.state.workspaces;
+    assert_eq!(workspaces.current, 1);
+    assert_eq!(workspaces.active_id_on(&f.output(1)), Some(1));
+    assert_eq!(workspaces.active_id_on(&f.output(0)), Some(0));
+}
+
+#[test]
+fn visible_workspace_is_focused_on_its_output() {
+    let mut f = two_outputs();
+
+    f.data.state.handle_action(magma::config::Action::Workspace(2));
+
+    let workspaces = &f.data.state.workspaces;
+    assert_eq!(workspaces.current, 2);
+    assert_eq!(workspaces.active_id_on(&f.output(0)), Some(0));
+    assert_eq!(workspaces.active_id_on(&f.output(1)), Some(2));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{keys, rect, Fixture};
+use magma::config::Action;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::State;
+
+#[test]
+fn switching_workspace_notifies_ipc() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.client(client).subscribe_workspaces();
+    f.wait_until(|f| f.client(client).state.active_workspace == Some(0));
+
+    f.press_keys(&[keys::SUPER, keys::KEY_2]);
+
+    assert_eq!(f.data.state.workspaces.current, 1);
+    assert_eq!(f.client(client).state.active_workspace, Some(1));
+}
+
+#[test]
+fn moving_window_updates_occupied_workspaces() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    f.client(client).subscribe_workspaces();
+    let toplevels = f.spawn_toplevels(client, 2);
+    assert_eq!(f.client(client).state.occupied_workspaces, Some(vec![0]));
+
+    f.move_pointer_to((100.0, 100.0));
+    f.press_keys(&[keys::SUPER, keys::SHIFT, keys::KEY_2]);
+
+    assert_eq!(f.client(client).state.occupied_workspaces, Some(vec![0, 1]));
+    assert_eq!(f.window_rects(), vec![rect(10, 10, 1900, 1060)]);
+    assert_eq!(f.window_rect(client, toplevels[1]), rect(10, 10, 1900, 1060));
+}
+
+#[test]
+fn swipe_switches_workspace() {
+    let mut f = Fixture::new();
+    let output = f.output(0);
+
+    f.swipe(3, (-300.0, 0.0));
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(1));
+
+    f.swipe(3, (300.0, 0.0));
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(0));
+}
+
+#[test]
+fn workspaces_follow_swipe() {
+    let mut f = Fixture::new();
+    let output = f.output(0);
+    let shifts = |f: &Fixture| -> Vec<i32> {
+        f.data
+            .state
+            .workspaces
+            .rendered_on(&output)
+            .iter()
+            .map(|(_, shift)| shift.x)
+            .collect()
+    };
+
+    f.swipe_begin(3);
+    f.swipe_update((-100.0, 0.0));
+    assert_eq!(shifts(&f), vec![-100, 1820]);
+    f.swipe_update((-50.0, 10.0));
+    assert_eq!(shifts(&f), vec![-150, 1770]);
+
+    // past the first workspace there is nothing to reveal
+    f.swipe_update((300.0, 0.0));
+    assert_eq!(shifts(&f), vec![0]);
+
+    f.swipe_end(true);
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(0));
+}
+
+#[test]
+fn short_or_cancelled_swipe_stays() {
+    let mut f = Fixture::new();
+    let output = f.output(0);
+
+    f.swipe(3, (-100.0, 0.0));
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(0));
+
+    f.swipe_begin(3);
+    f.swipe_update((-300.0, 0.0));
+    f.swipe_end(true);
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(0));
+
+    // no gesture is bound to four fingers
+    f.swipe(4, (-300.0, 0.0));
+    assert_eq!(f.data.state.workspaces.active_id_on(&output), Some(0));
+}
+
+#[test]
+fn vertical_swipe_runs_action() {
+    let mut f = Fixture::new();
+    let client = f.add_client();
+    let toplevels = f.spawn_toplevels(client, 1);
+
+    f.swipe(3, (20.0, -200.0));
+
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(0));
+    assert!(f.client(client).state.toplevels[toplevels[0]]
+        .states
+        .contains(&State::Maximized));
+}
+
+#[test]
+fn next_workspace_skips_other_outputs() {
+    let mut f = Fixture::with_outputs(&[(1920, 1080), (1920, 1080)]);
+
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(1)), Some(2));
+
+    f.data.state.handle_action(Action::NextWorkspace);
+    f.roundtrip();
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(1));
+
+    // the last one is shown on the other output
+    f.data.state.handle_action(Action::NextWorkspace);
+    f.roundtrip();
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(1));
+
+    f.data.state.handle_action(Action::PrevWorkspace);
+    f.roundtrip();
+    assert_eq!(f.data.state.workspaces.active_id_on(&f.output(0)), Some(0));
+}